@@ -0,0 +1,495 @@
+pub mod bundle;
+pub mod fingerprint;
+mod report;
+
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::Help;
+use eyre::{eyre, Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use log::{info, warn};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    consts::{BUILD_RECORDS_DIR, CONFIG_FILE_NAME, DEFAULT_BUILD_DIR},
+    email::{self, EmailCss},
+    render::{Renderer, TEMPLATE_DIRS, TEMPLATE_SUFFIX},
+    server::{
+        cache::CacheMode,
+        is_html,
+        reload::url_path,
+        rules::{HEADERS_FILE, REDIRECTS_FILE},
+        ServerArgs,
+    },
+    tailwind::TailwindArgs,
+};
+
+/// Files only needed to develop the site, never copied to the output
+static SOURCE_FILES: &[&str] = &[
+    CONFIG_FILE_NAME,
+    "tailwind.config.js",
+    "tailwind.config.cjs",
+    "tailwind.config.mjs",
+    "tailwind.config.ts",
+    "package.json",
+    "package-lock.json",
+    "pnpm-lock.yaml",
+    "yarn.lock",
+    "bun.lockb",
+    "bun.lock",
+    "node_modules",
+];
+
+#[derive(Debug, Clone)]
+pub struct BuildArgs {
+    pub root_dir: PathBuf,
+    /// Where the site is written to, emptied first when an earlier build wrote to it
+    pub out_dir: PathBuf,
+    /// Compiles the css, its output is written to the same place inside `out_dir`
+    pub tailwind: TailwindArgs,
+    /// Patterns from `server.hidden`, matching files are left out like the server hides them
+    pub hidden: Vec<String>,
+    /// Add a content hash to the names of css, js, images and fonts so they can be cached forever
+    pub fingerprint: bool,
+    /// Inline the css into the `style` attributes of every html page instead of writing it out
+    pub email: bool,
+}
+
+/// Builds the site for production: minified css, rendered pages and every other file copied
+/// as-is, ready to deploy to any static host
+pub fn run(args: BuildArgs) -> Result<()> {
+    let root_dir = std::fs::canonicalize(&args.root_dir)
+        .wrap_err_with(|| format!("Unable to find root dir: {}", args.root_dir.display()))?;
+
+    let out_dir = prepare_out_dir(&root_dir, &args.out_dir, &BUILD_RECORDS_DIR)?;
+    let mut written = Vec::new();
+
+    // recorded even when the build fails part way, so the next one can still empty the out dir
+    let built = write_site(&args, &root_dir, &out_dir, &mut written);
+    record_build(&BUILD_RECORDS_DIR, &out_dir, &written)?;
+    built?;
+
+    report::print(&out_dir, &written);
+
+    Ok(())
+}
+
+fn write_site(
+    args: &BuildArgs,
+    root_dir: &Path,
+    out_dir: &Path,
+    written: &mut Vec<PathBuf>,
+) -> Result<()> {
+    let hidden = hidden_set(&args.hidden)?;
+
+    // the css is written to the same url it is served at in development
+    let stylesheet = url_path(&args.tailwind.output, &args.root_dir);
+
+    // emails can't link to a stylesheet, so it's only compiled to be inlined
+    let email_css = if args.email {
+        info!("Building css to inline into html pages");
+
+        let css = compile_css(&args.tailwind)?.unwrap_or_default();
+        Some(EmailCss::parse(&css))
+    } else {
+        let css_output = out_dir.join(stylesheet.trim_start_matches('/'));
+
+        if let Some(parent) = css_output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        info!("Building minified css to {}", css_output.display());
+
+        crate::tailwind::start(TailwindArgs {
+            output: css_output.clone(),
+            watch: false,
+            minify: true,
+            reloader: None,
+            ..args.tailwind.clone()
+        })?;
+
+        written.push(css_output);
+        None
+    };
+
+    // an earlier build inside the project is never part of the site
+    let skipped = [
+        args.tailwind.input.clone(),
+        args.tailwind.output.clone(),
+        root_dir.join(DEFAULT_BUILD_DIR),
+        out_dir.to_path_buf(),
+    ]
+    .into_iter()
+    .filter_map(|path| std::fs::canonicalize(path).ok())
+    .collect::<Vec<_>>();
+
+    let renderer = Renderer::new(root_dir).stylesheet(Some(stylesheet.clone()));
+    let mut email_warnings = BTreeSet::new();
+
+    let walker = WalkBuilder::new(root_dir)
+        .hidden(true)
+        .git_ignore(true)
+        .require_git(false)
+        .filter_entry(move |entry| {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy();
+
+            let is_source = skipped.iter().any(|skipped| path == skipped)
+                || SOURCE_FILES.contains(&name.as_ref())
+                || name.ends_with(bundle::BUNDLED_SUFFIX)
+                || (entry.depth() == 1 && TEMPLATE_DIRS.contains(&name.as_ref()));
+
+            !is_source
+        })
+        .build();
+
+    for entry in walker {
+        let entry = entry?;
+        let path = entry.path();
+
+        if !entry
+            .file_type()
+            .is_some_and(|file_type| file_type.is_file())
+        {
+            continue;
+        }
+
+        let relative = path.strip_prefix(root_dir)?;
+
+        if is_hidden(&hidden, relative) {
+            continue;
+        }
+
+        let (target, contents) = build_file(&renderer, path, relative)?;
+
+        let contents = match &email_css {
+            Some(css) if is_html(&target) => {
+                let html = match contents {
+                    Some(html) => html,
+                    None => std::fs::read_to_string(path)?,
+                };
+
+                let inlined = email::inline(&html, css, Some(&stylesheet), &page_url(&target))
+                    .wrap_err_with(|| {
+                        format!("Unable to inline css into {}", relative.display())
+                    })?;
+
+                email_warnings.extend(inlined.warnings);
+                Some(inlined.html)
+            }
+            _ => contents,
+        };
+
+        let target = out_dir.join(target);
+
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        match contents {
+            Some(contents) => std::fs::write(&target, contents)?,
+            None => {
+                std::fs::copy(path, &target)?;
+            }
+        }
+
+        written.push(target);
+    }
+
+    for warning in &email_warnings {
+        warn!("{warning}");
+    }
+
+    if args.fingerprint {
+        let manifest = fingerprint::run(out_dir, written)?;
+        let manifest_path = out_dir.join(fingerprint::MANIFEST_FILE);
+
+        std::fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)?;
+        written.push(manifest_path);
+    }
+
+    Ok(())
+}
+
+/// Serves a build like a production host would: fingerprinted files are cached forever,
+/// responses are compressed and there's no livereload
+pub async fn preview(args: ServerArgs) -> Result<()> {
+    if !args.root_dir.is_dir() {
+        return Err(eyre!("No build found in {}", args.root_dir.display()))
+            .suggestion("Run `easywind build` first");
+    }
+
+    crate::server::start(ServerArgs {
+        cache: CacheMode::Immutable,
+        compress: true,
+        livereload: false,
+        // the build is served on its own, not the dev backend, and its pages are already
+        // rendered and inlined
+        proxy: None,
+        stylesheet: None,
+        tailwind_input: None,
+        email: false,
+        ..args
+    })
+    .await
+}
+
+// projects without an input css still build, with no css
+fn compile_css(tailwind: &TailwindArgs) -> Result<Option<String>> {
+    if !tailwind.input.exists() {
+        return Ok(None);
+    }
+
+    let dir = tempfile::tempdir()?;
+    let output = dir.path().join("app.css");
+
+    crate::tailwind::start(TailwindArgs {
+        output: output.clone(),
+        watch: false,
+        minify: true,
+        reloader: None,
+        ..tailwind.clone()
+    })?;
+
+    Ok(Some(std::fs::read_to_string(output)?))
+}
+
+/// Where a file ends up in the output and its rendered contents, `None` when it's copied as-is
+fn build_file(
+    renderer: &Renderer,
+    path: &Path,
+    relative: &Path,
+) -> Result<(PathBuf, Option<String>)> {
+    if Renderer::is_markdown(path) {
+        let target = relative.with_extension("html");
+        let html = renderer
+            .render_markdown(path, &page_url(&target))
+            .map_err(|error| render_error(relative, error))?;
+
+        return Ok((target, Some(html)));
+    }
+
+    if Renderer::is_page(path) {
+        let target = PathBuf::from(
+            relative
+                .to_string_lossy()
+                .trim_end_matches(TEMPLATE_SUFFIX)
+                .to_string(),
+        );
+
+        let html = renderer
+            .render(path, &page_url(&target))
+            .map_err(|error| render_error(relative, error))?;
+
+        return Ok((target, Some(html)));
+    }
+
+    Ok((relative.to_path_buf(), None))
+}
+
+// the url the page is served at, ex: `blog/index.html` -> `/blog/`
+fn page_url(target: &Path) -> String {
+    let path = target
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    match path.strip_suffix("index.html") {
+        Some(dir) => format!("/{dir}"),
+        None => format!("/{path}"),
+    }
+}
+
+fn render_error(relative: &Path, error: crate::render::RenderError) -> eyre::Report {
+    let report = eyre!("Unable to render {}: {}", relative.display(), error.message);
+
+    match error.detail {
+        Some(detail) => report.note(detail),
+        None => report,
+    }
+}
+
+// only a dir that holds nothing but the files of the last build to it is emptied, anything
+// else has to be empty so nothing is deleted by accident, ex: `--out src`
+fn prepare_out_dir(root_dir: &Path, out_dir: &Path, records_dir: &Path) -> Result<PathBuf> {
+    std::fs::create_dir_all(out_dir)
+        .wrap_err_with(|| format!("Unable to create out dir: {}", out_dir.display()))?;
+
+    let out_dir = std::fs::canonicalize(out_dir)?;
+
+    if root_dir.starts_with(&out_dir) {
+        return Err(eyre!(
+            "The out dir {} contains the project",
+            out_dir.display()
+        ))
+        .suggestion("Pick a directory inside the project, ex: `--out build`");
+    }
+
+    if std::fs::read_dir(&out_dir)?.next().is_none() {
+        return Ok(out_dir);
+    }
+
+    let built = std::fs::read_to_string(build_record(records_dir, &out_dir)).unwrap_or_default();
+    let built = built.lines().map(PathBuf::from).collect::<BTreeSet<_>>();
+
+    if let Some(file) = files_in(&out_dir)?
+        .into_iter()
+        .find(|file| !built.contains(file))
+    {
+        return Err(eyre!(
+            "The out dir {} has files that weren't written by easywind build, ex: {}",
+            out_dir.display(),
+            file.display()
+        ))
+        .suggestion("Remove it first, or pick an empty directory");
+    }
+
+    std::fs::remove_dir_all(&out_dir)?;
+    std::fs::create_dir_all(&out_dir)?;
+
+    Ok(out_dir)
+}
+
+fn record_build(records_dir: &Path, out_dir: &Path, written: &[PathBuf]) -> Result<()> {
+    let files = written
+        .iter()
+        .filter_map(|path| path.strip_prefix(out_dir).ok())
+        .map(|relative| format!("{}\n", relative.display()))
+        .collect::<String>();
+
+    std::fs::create_dir_all(records_dir)?;
+    std::fs::write(build_record(records_dir, out_dir), files)
+        .wrap_err("Unable to record the files written by the build")?;
+
+    Ok(())
+}
+
+// one file per out dir, named after a hash of its path
+fn build_record(records_dir: &Path, out_dir: &Path) -> PathBuf {
+    let hash = format!(
+        "{:x}",
+        Sha256::digest(out_dir.as_os_str().as_encoded_bytes())
+    );
+    records_dir.join(format!("{}.txt", &hash[..16]))
+}
+
+// every file in the dir, relative to it
+fn files_in(dir: &Path) -> Result<BTreeSet<PathBuf>> {
+    let mut files = BTreeSet::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current)? {
+            let entry = entry?;
+
+            match entry.file_type()?.is_dir() {
+                true => pending.push(entry.path()),
+                false => {
+                    files.insert(entry.path().strip_prefix(dir)?.to_path_buf());
+                }
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+// `_redirects` and `_headers` are hidden from the dev server but the static host needs them
+fn hidden_set(hidden: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+
+    for pattern in hidden {
+        if pattern == REDIRECTS_FILE || pattern == HEADERS_FILE {
+            continue;
+        }
+
+        builder.add(
+            Glob::new(pattern)
+                .wrap_err_with(|| format!("Invalid pattern in server.hidden: {pattern}"))?,
+        );
+    }
+
+    Ok(builder.build()?)
+}
+
+fn is_hidden(hidden: &GlobSet, relative: &Path) -> bool {
+    hidden.is_match(relative)
+        || relative
+            .components()
+            .any(|component| hidden.is_match(component.as_os_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Dirs {
+        _temp: tempfile::TempDir,
+        root: PathBuf,
+        out: PathBuf,
+        records: PathBuf,
+    }
+
+    fn dirs() -> Dirs {
+        let temp = tempfile::tempdir().unwrap();
+        let base = std::fs::canonicalize(temp.path()).unwrap();
+        let root = base.join("site");
+        std::fs::create_dir_all(&root).unwrap();
+
+        Dirs {
+            out: root.join("build"),
+            records: base.join("records"),
+            root,
+            _temp: temp,
+        }
+    }
+
+    #[test]
+    fn never_empties_a_dir_it_did_not_create() {
+        let dirs = dirs();
+        std::fs::create_dir_all(dirs.out.join("nested")).unwrap();
+        std::fs::write(dirs.out.join("nested/notes.txt"), "mine").unwrap();
+
+        assert!(prepare_out_dir(&dirs.root, &dirs.out, &dirs.records).is_err());
+        assert!(dirs.out.join("nested/notes.txt").is_file());
+    }
+
+    #[test]
+    fn empties_the_output_of_an_earlier_build() {
+        let dirs = dirs();
+        let out = prepare_out_dir(&dirs.root, &dirs.out, &dirs.records).unwrap();
+
+        std::fs::create_dir_all(out.join("blog")).unwrap();
+        std::fs::write(out.join("blog/index.html"), "").unwrap();
+        record_build(&dirs.records, &out, &[out.join("blog/index.html")]).unwrap();
+
+        prepare_out_dir(&dirs.root, &dirs.out, &dirs.records).unwrap();
+        assert!(std::fs::read_dir(&out).unwrap().next().is_none());
+    }
+
+    #[test]
+    fn keeps_a_build_that_has_other_files_added() {
+        let dirs = dirs();
+        let out = prepare_out_dir(&dirs.root, &dirs.out, &dirs.records).unwrap();
+
+        std::fs::write(out.join("index.html"), "").unwrap();
+        record_build(&dirs.records, &out, &[out.join("index.html")]).unwrap();
+        std::fs::write(out.join("notes.txt"), "mine").unwrap();
+
+        assert!(prepare_out_dir(&dirs.root, &dirs.out, &dirs.records).is_err());
+        assert!(out.join("notes.txt").is_file());
+    }
+
+    #[test]
+    fn refuses_an_out_dir_that_contains_the_project() {
+        let dirs = dirs();
+        let parent = dirs.root.parent().unwrap();
+
+        assert!(prepare_out_dir(&dirs.root, parent, &dirs.records).is_err());
+    }
+}
@@ -0,0 +1,355 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use color_eyre::Help;
+use eyre::{eyre, Context, Result};
+use log::{info, warn};
+use lol_html::{element, html_content::ContentType, rewrite_str, text, RewriteStrSettings};
+use percent_encoding::percent_decode_str;
+
+use super::fingerprint::{attribute, normalize, rewrite_css_urls, set_attribute};
+use crate::{render::Renderer, server::reload::url_path, tailwind::TailwindArgs};
+
+/// Images and fonts bigger than this are left as links, in kB
+pub static DEFAULT_INLINE_LIMIT: u64 = 1024;
+
+/// Added to the name of bundled pages, these are never part of a build
+pub static BUNDLED_SUFFIX: &str = ".bundled.html";
+
+// the livereload script and other files only the dev server has
+static INTERNAL_PATH_PREFIX: &str = "/__internal_only_easywind";
+
+static MEDIA_SELECTOR: &str = "img[src], source[src], video[src], audio[src], track[src], \
+     input[src], video[poster], link[rel~=icon][href], link[rel~=apple-touch-icon][href]";
+
+#[derive(Debug, Clone)]
+pub struct BundleArgs {
+    pub root_dir: PathBuf,
+    /// An html page, page template or markdown file inside the root dir
+    pub page: PathBuf,
+    /// Defaults to `<name>.bundled.html` next to the page
+    pub output: Option<PathBuf>,
+    /// Compiles the css that's inlined in place of the link to it
+    pub tailwind: TailwindArgs,
+    /// Images and fonts bigger than this are left as links, in kB
+    pub inline_limit: u64,
+}
+
+/// Bundles a page into a single html file that works offline: stylesheets and scripts are
+/// inlined, images and fonts become data uris and the livereload script is removed
+pub fn run(args: BundleArgs) -> Result<()> {
+    let root_dir = std::fs::canonicalize(&args.root_dir)
+        .wrap_err_with(|| format!("Unable to find root dir: {}", args.root_dir.display()))?;
+
+    let page = std::fs::canonicalize(&args.page)
+        .wrap_err_with(|| format!("Unable to find page: {}", args.page.display()))?;
+
+    let Ok(relative) = page.strip_prefix(&root_dir) else {
+        return Err(eyre!("{} is not in the project", page.display()))
+            .suggestion(format!("Pick a page inside {}", root_dir.display()));
+    };
+
+    let stylesheet = url_path(&args.tailwind.output, &args.root_dir);
+    let renderer = Renderer::new(&root_dir).stylesheet(Some(stylesheet.clone()));
+
+    let (target, rendered) = super::build_file(&renderer, &page, relative)?;
+    let html = match rendered {
+        Some(html) => html,
+        None => std::fs::read_to_string(&page)?,
+    };
+
+    let compiled_css = super::compile_css(&args.tailwind)?;
+
+    let mut inliner = Inliner {
+        root_dir: root_dir.clone(),
+        stylesheet,
+        compiled_css,
+        inline_limit: args.inline_limit * 1024,
+        warnings: BTreeSet::new(),
+    };
+
+    // relative urls in the page are resolved from its directory
+    let page_dir = url_path(&root_dir.join(&target), &root_dir)
+        .trim_start_matches('/')
+        .rsplit_once('/')
+        .map(|(page_dir, _)| page_dir.to_string())
+        .unwrap_or_default();
+
+    let bundled = inliner
+        .inline_html(&html, &page_dir)
+        .wrap_err_with(|| format!("Unable to bundle {}", relative.display()))?;
+
+    let output = args
+        .output
+        .unwrap_or_else(|| bundled_name(&root_dir, &target));
+    std::fs::write(&output, &bundled)
+        .wrap_err_with(|| format!("Unable to write {}", output.display()))?;
+
+    for warning in &inliner.warnings {
+        warn!("{warning}");
+    }
+
+    info!(
+        "Bundled {} into {} ({})",
+        relative.display(),
+        output.display(),
+        super::report::format_size(bundled.len() as u64)
+    );
+
+    Ok(())
+}
+
+// `blog/index.html` -> `blog/index.bundled.html`
+fn bundled_name(root_dir: &Path, target: &Path) -> PathBuf {
+    let stem = target
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    root_dir.join(target.with_file_name(format!("{stem}{BUNDLED_SUFFIX}")))
+}
+
+struct Inliner {
+    root_dir: PathBuf,
+    /// Url of the compiled tailwind css, replaced with `compiled_css`
+    stylesheet: String,
+    compiled_css: Option<String>,
+    /// In bytes
+    inline_limit: u64,
+    /// Files that couldn't be inlined, logged once at the end
+    warnings: BTreeSet<String>,
+}
+
+impl Inliner {
+    fn inline_html(&mut self, html: &str, dir: &str) -> Result<String> {
+        let this = std::cell::RefCell::new(self);
+        let mut style = String::new();
+
+        let settings = RewriteStrSettings {
+            element_content_handlers: vec![
+                element!("script[src]", |el| {
+                    let Some(src) = attribute(el, "src") else {
+                        return Ok(());
+                    };
+
+                    if src.starts_with(INTERNAL_PATH_PREFIX) {
+                        el.remove();
+                        return Ok(());
+                    }
+
+                    let mut this = this.borrow_mut();
+
+                    if let Some(js) = this.read_text(&src, dir) {
+                        el.remove_attribute("src");
+                        el.set_inner_content(
+                            &js.replace("</script", "<\\/script"),
+                            ContentType::Html,
+                        );
+                    }
+
+                    Ok(())
+                }),
+                element!("link[rel~=stylesheet][href]", |el| {
+                    let Some(href) = attribute(el, "href") else {
+                        return Ok(());
+                    };
+
+                    if href.starts_with(INTERNAL_PATH_PREFIX) {
+                        el.remove();
+                        return Ok(());
+                    }
+
+                    let mut this = this.borrow_mut();
+
+                    if let Some(css) = this.stylesheet_contents(&href, dir) {
+                        let media = el
+                            .get_attribute("media")
+                            .map(|media| format!(r#" media="{media}""#))
+                            .unwrap_or_default();
+
+                        el.replace(&format!("<style{media}>{css}</style>"), ContentType::Html);
+                    }
+
+                    Ok(())
+                }),
+                element!(MEDIA_SELECTOR, |el| {
+                    let mut this = this.borrow_mut();
+
+                    for name in ["src", "poster", "href"] {
+                        let Some(url) = attribute(el, name) else {
+                            continue;
+                        };
+
+                        if let Some(data_uri) = this.data_uri(&url, dir) {
+                            set_attribute(el, name, &url, &data_uri)?;
+                        }
+                    }
+
+                    Ok(())
+                }),
+                element!("img[srcset], source[srcset]", |el| {
+                    let Some(srcset) = attribute(el, "srcset") else {
+                        return Ok(());
+                    };
+
+                    let inlined = this.borrow_mut().inline_srcset(&srcset, dir);
+                    set_attribute(el, "srcset", &srcset, &inlined)?;
+
+                    Ok(())
+                }),
+                element!("[style]", |el| {
+                    if let Some(css) = attribute(el, "style") {
+                        let inlined = this.borrow_mut().inline_css(&css, dir);
+                        set_attribute(el, "style", &css, &inlined)?;
+                    }
+
+                    Ok(())
+                }),
+                // text can arrive in chunks, the whole block is needed to find its urls
+                text!("style", |chunk| {
+                    style.push_str(chunk.as_str());
+
+                    if chunk.last_in_text_node() {
+                        let css = this.borrow_mut().inline_css(&style, dir);
+                        chunk.replace(&css, ContentType::Html);
+                        style.clear();
+                    } else {
+                        chunk.remove();
+                    }
+
+                    Ok(())
+                }),
+            ],
+            ..RewriteStrSettings::new()
+        };
+
+        rewrite_str(html, settings).map_err(|error| eyre!("{error}"))
+    }
+
+    // the compiled css for the project's stylesheet, any other css is read from disk
+    fn stylesheet_contents(&mut self, href: &str, dir: &str) -> Option<String> {
+        let url = href.split(['?', '#']).next().unwrap_or_default();
+
+        // `dist/app.css` from the root page is the same file as `/dist/app.css`
+        let logical = match url.strip_prefix('/') {
+            Some(absolute) => normalize(absolute),
+            None => normalize(&format!("{dir}/{url}")),
+        };
+
+        let is_project_stylesheet =
+            logical.is_some_and(|logical| logical == self.stylesheet.trim_start_matches('/'));
+
+        let (css, css_path) = match &self.compiled_css {
+            Some(compiled) if is_project_stylesheet => (compiled.clone(), self.stylesheet.clone()),
+            _ => {
+                let path = self.local_path(href, dir)?;
+                let css = self.read_text(href, dir)?;
+                (css, url_path(&path, &self.root_dir))
+            }
+        };
+
+        // urls in the css are relative to the css file, not the page
+        let css_dir = css_path
+            .trim_start_matches('/')
+            .rsplit_once('/')
+            .map(|(css_dir, _)| css_dir.to_string())
+            .unwrap_or_default();
+
+        Some(
+            self.inline_css(&css, &css_dir)
+                .replace("</style", "<\\/style"),
+        )
+    }
+
+    fn inline_css(&mut self, css: &str, dir: &str) -> String {
+        rewrite_css_urls(css, |url| self.data_uri(url, dir))
+    }
+
+    // `a.png 1x, a@2x.png 2x`
+    fn inline_srcset(&mut self, srcset: &str, dir: &str) -> String {
+        srcset
+            .split(',')
+            .map(|candidate| {
+                let candidate = candidate.trim();
+                let (url, descriptor) = candidate.split_once(' ').unwrap_or((candidate, ""));
+                let url = self.data_uri(url, dir).unwrap_or_else(|| url.to_string());
+
+                match descriptor.trim() {
+                    "" => url,
+                    descriptor => format!("{url} {descriptor}"),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn read_text(&mut self, url: &str, dir: &str) -> Option<String> {
+        let path = self.local_path(url, dir)?;
+
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => Some(contents),
+            Err(error) => {
+                self.warnings
+                    .insert(format!("Unable to read {}: {error}", path.display()));
+                None
+            }
+        }
+    }
+
+    fn data_uri(&mut self, url: &str, dir: &str) -> Option<String> {
+        let path = self.local_path(url, dir)?;
+        let size = path.metadata().ok()?.len();
+
+        if size > self.inline_limit {
+            self.warnings.insert(format!(
+                "{url} is {} kB, bigger than the inline limit, so it won't load offline. \
+                 Raise it with --inline-limit",
+                size / 1024
+            ));
+            return None;
+        }
+
+        let mime = mime_guess::from_path(&path).first_or_octet_stream();
+        let contents = std::fs::read(&path).ok()?;
+
+        Some(format!("data:{mime};base64,{}", STANDARD.encode(contents)))
+    }
+
+    /// The file a url points to, `None` for remote urls and ones that aren't found
+    fn local_path(&mut self, url: &str, dir: &str) -> Option<PathBuf> {
+        if url.is_empty() || url.starts_with('#') || url.starts_with("data:") {
+            return None;
+        }
+
+        if url.starts_with("//") || url.contains("://") {
+            self.warnings
+                .insert(format!("{url} is remote, it won't load offline"));
+            return None;
+        }
+
+        if url.contains(':') {
+            return None;
+        }
+
+        let path = url.split(['?', '#']).next().unwrap_or_default();
+        let path = percent_decode_str(path).decode_utf8_lossy();
+
+        let logical = match path.strip_prefix('/') {
+            Some(absolute) => normalize(absolute),
+            None => normalize(&format!("{dir}/{path}")),
+        };
+
+        let file = logical
+            .map(|logical| self.root_dir.join(logical))
+            .filter(|file| file.is_file());
+
+        if file.is_none() {
+            self.warnings.insert(format!("{url} was not found"));
+        }
+
+        file
+    }
+}
@@ -0,0 +1,426 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use eyre::{eyre, Context, Result};
+use lol_html::{element, rewrite_str, RewriteStrSettings};
+use sha2::{Digest, Sha256};
+
+/// Written to the out dir, maps the original path of every fingerprinted file to its new name
+pub static MANIFEST_FILE: &str = "asset-manifest.json";
+
+/// Number of hex characters of the content hash added to file names, ex: `app.3f9a1c2b.css`
+pub static HASH_LEN: usize = 8;
+
+static STYLESHEETS: &[&str] = &["css"];
+static SCRIPTS: &[&str] = &["js", "mjs"];
+static MEDIA: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "svg", "webp", "avif", "ico", "woff", "woff2", "ttf", "otf",
+];
+
+static SRC_SELECTOR: &str =
+    "script[src], img[src], source[src], video[src], audio[src], track[src], input[src]";
+
+// browsers request these by name, so they keep it
+static FIXED_NAMES: &[&str] = &["favicon.ico"];
+
+/// Original paths mapped to fingerprinted ones, relative to the out dir with `/` separators
+pub type Manifest = BTreeMap<String, String>;
+
+/// Copies css, js, images and fonts in the out dir to a name with a hash of their contents,
+/// then points the references in css `url()`s and `@import`s and in html attributes to the
+/// copies. The originals stay, so references that aren't rewritten, ex: a js `import`, a
+/// `<style>` block or a path in `_headers`, still work, they're just not cached forever.
+///
+/// Media is hashed first because stylesheets point to it, which changes their hash
+pub(super) fn run(out_dir: &Path, written: &mut Vec<PathBuf>) -> Result<Manifest> {
+    let mut manifest = Manifest::new();
+    let mut copies = Vec::new();
+
+    for path in written.iter().filter(|path| has_extension(path, MEDIA)) {
+        copies.extend(fingerprint(out_dir, path, &mut manifest)?);
+    }
+
+    for path in stylesheets_in_import_order(out_dir, written)? {
+        let css = std::fs::read_to_string(path)?;
+        let css = rewrite_css(&css, &relative_dir(out_dir, path), &manifest);
+        std::fs::write(path, css)?;
+
+        copies.extend(fingerprint(out_dir, path, &mut manifest)?);
+    }
+
+    for path in written.iter().filter(|path| has_extension(path, SCRIPTS)) {
+        copies.extend(fingerprint(out_dir, path, &mut manifest)?);
+    }
+
+    for path in written.iter().filter(|path| has_extension(path, &["html"])) {
+        let html = std::fs::read_to_string(path)?;
+        let html = rewrite_html(&html, &relative_dir(out_dir, path), &manifest)
+            .wrap_err_with(|| format!("Unable to rewrite asset urls in {}", path.display()))?;
+
+        std::fs::write(path, html)?;
+    }
+
+    written.extend(copies);
+
+    Ok(manifest)
+}
+
+// copies the file to its hashed name and records it in the manifest
+fn fingerprint(out_dir: &Path, path: &Path, manifest: &mut Manifest) -> Result<Option<PathBuf>> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    if FIXED_NAMES.contains(&name.as_str()) {
+        return Ok(None);
+    }
+
+    let contents = std::fs::read(path)?;
+    let hash = format!("{:x}", Sha256::digest(&contents));
+
+    let hashed_name = match name.rsplit_once('.') {
+        Some((stem, extension)) => format!("{stem}.{}.{extension}", &hash[..HASH_LEN]),
+        None => format!("{name}.{}", &hash[..HASH_LEN]),
+    };
+
+    let hashed = path.with_file_name(hashed_name);
+    std::fs::write(&hashed, contents)?;
+
+    manifest.insert(url_path(out_dir, path), url_path(out_dir, &hashed));
+
+    Ok(Some(hashed))
+}
+
+/// Stylesheets with the ones they reference first, so the hash of every stylesheet is known
+/// by the time the references to it are rewritten
+fn stylesheets_in_import_order<'a>(
+    out_dir: &Path,
+    written: &'a [PathBuf],
+) -> Result<Vec<&'a PathBuf>> {
+    let stylesheets = written
+        .iter()
+        .filter(|path| has_extension(path, STYLESHEETS))
+        .map(|path| (url_path(out_dir, path), path))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut imports = BTreeMap::new();
+
+    for (url, path) in &stylesheets {
+        let css = std::fs::read_to_string(path)?;
+        let dir = relative_dir(out_dir, path);
+
+        let imported = css_references(&css)
+            .iter()
+            .filter_map(|reference| logical_path(reference, &dir))
+            .filter(|logical| stylesheets.contains_key(logical))
+            .collect::<Vec<_>>();
+
+        imports.insert(url.as_str(), imported);
+    }
+
+    let mut ordered = Vec::new();
+    let mut visited = BTreeSet::new();
+
+    for url in stylesheets.keys() {
+        add_after_imports(url, &imports, &mut visited, &mut ordered);
+    }
+
+    Ok(ordered
+        .into_iter()
+        .filter_map(|url| stylesheets.get(url.as_str()).copied())
+        .collect())
+}
+
+// depth first, a stylesheet that was already visited is skipped so import cycles end
+fn add_after_imports(
+    url: &str,
+    imports: &BTreeMap<&str, Vec<String>>,
+    visited: &mut BTreeSet<String>,
+    ordered: &mut Vec<String>,
+) {
+    if !visited.insert(url.to_string()) {
+        return;
+    }
+
+    for import in imports.get(url).into_iter().flatten() {
+        add_after_imports(import, imports, visited, ordered);
+    }
+
+    ordered.push(url.to_string());
+}
+
+fn rewrite_css(css: &str, dir: &str, manifest: &Manifest) -> String {
+    let css = rewrite_css_urls(css, |url| rewrite_url(url, dir, manifest));
+    rewrite_css_imports(&css, |url| rewrite_url(url, dir, manifest))
+}
+
+// every url in `url()`s and `@import`s
+fn css_references(css: &str) -> Vec<String> {
+    let mut references = Vec::new();
+
+    let mut collect = |url: &str| {
+        references.push(url.to_string());
+        None
+    };
+
+    rewrite_css_urls(css, &mut collect);
+    rewrite_css_imports(css, &mut collect);
+
+    references
+}
+
+/// Replaces the url in every `url()` of the css that `rewrite` returns a new one for
+pub(super) fn rewrite_css_urls(
+    css: &str,
+    mut rewrite: impl FnMut(&str) -> Option<String>,
+) -> String {
+    let mut rewritten = String::with_capacity(css.len());
+    let mut rest = css;
+
+    while let Some(start) = rest.find("url(") {
+        let (before, after) = rest.split_at(start + "url(".len());
+        rewritten.push_str(before);
+
+        let Some(end) = after.find(')') else {
+            rest = after;
+            break;
+        };
+
+        let value = &after[..end];
+        let url = value.trim().trim_matches(|c| c == '"' || c == '\'');
+
+        match rewrite(url) {
+            Some(new_url) => rewritten.push_str(&value.replacen(url, &new_url, 1)),
+            None => rewritten.push_str(value),
+        }
+
+        rest = &after[end..];
+    }
+
+    rewritten.push_str(rest);
+    rewritten
+}
+
+/// Replaces the url of every `@import "..."` of the css that `rewrite` returns a new one for,
+/// `@import url(...)` is handled by `rewrite_css_urls`
+fn rewrite_css_imports(css: &str, mut rewrite: impl FnMut(&str) -> Option<String>) -> String {
+    let mut rewritten = String::with_capacity(css.len());
+    let mut rest = css;
+
+    while let Some(start) = rest.find("@import") {
+        let (before, after) = rest.split_at(start + "@import".len());
+        rewritten.push_str(before);
+
+        let trimmed = after.trim_start();
+        let Some(quote) = trimmed.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            rest = after;
+            continue;
+        };
+
+        let Some(end) = trimmed[1..].find(quote) else {
+            rest = after;
+            break;
+        };
+
+        let value_start = after.len() - trimmed.len() + 1;
+        let value_end = value_start + end;
+        let url = &after[value_start..value_end];
+
+        rewritten.push_str(&after[..value_start]);
+        rewritten.push_str(&rewrite(url).unwrap_or_else(|| url.to_string()));
+        rest = &after[value_end..];
+    }
+
+    rewritten.push_str(rest);
+    rewritten
+}
+
+fn rewrite_html(html: &str, dir: &str, manifest: &Manifest) -> Result<String> {
+    let rewrite_attribute = |element: &mut lol_html::html_content::Element, name: &str| {
+        let Some(url) = attribute(element, name) else {
+            return Ok(());
+        };
+
+        match rewrite_url(&url, dir, manifest) {
+            Some(hashed) => set_attribute(element, name, &url, &hashed),
+            None => Ok(()),
+        }
+    };
+
+    let settings = RewriteStrSettings {
+        element_content_handlers: vec![
+            element!("link[href], a[href]", |el| Ok(rewrite_attribute(
+                el, "href"
+            )?)),
+            element!(SRC_SELECTOR, |el| Ok(rewrite_attribute(el, "src")?)),
+            element!("video[poster]", |el| Ok(rewrite_attribute(el, "poster")?)),
+            element!("meta[content]", |el| Ok(rewrite_attribute(el, "content")?)),
+            element!("[style]", |el| {
+                if let Some(style) = attribute(el, "style") {
+                    set_attribute(el, "style", &style, &rewrite_css(&style, dir, manifest))?;
+                }
+
+                Ok(())
+            }),
+            element!("img[srcset], source[srcset]", |el| {
+                if let Some(srcset) = attribute(el, "srcset") {
+                    let rewritten = rewrite_srcset(&srcset, dir, manifest);
+                    set_attribute(el, "srcset", &srcset, &rewritten)?;
+                }
+
+                Ok(())
+            }),
+        ],
+        ..RewriteStrSettings::new()
+    };
+
+    rewrite_str(html, settings).map_err(|error| eyre!("{error}"))
+}
+
+/// The value of an attribute with its character references decoded, ex: `&#x2f;` in
+/// `href="{{ stylesheet }}"`
+pub(crate) fn attribute(element: &lol_html::html_content::Element, name: &str) -> Option<String> {
+    let value = element.get_attribute(name)?;
+    Some(html_escape::decode_html_entities(&value).into_owned())
+}
+
+/// Writes back a value read with [`attribute`], only when it changed so the original markup is
+/// kept otherwise. `&` is escaped again since only `"` is escaped when it's written
+pub(crate) fn set_attribute(
+    element: &mut lol_html::html_content::Element,
+    name: &str,
+    original: &str,
+    value: &str,
+) -> Result<(), lol_html::errors::AttributeNameError> {
+    if value == original {
+        return Ok(());
+    }
+
+    element.set_attribute(name, &value.replace('&', "&amp;"))
+}
+
+// `a.png 1x, a@2x.png 2x`
+fn rewrite_srcset(srcset: &str, dir: &str, manifest: &Manifest) -> String {
+    srcset
+        .split(',')
+        .map(|candidate| {
+            let candidate = candidate.trim();
+            let (url, descriptor) = candidate.split_once(' ').unwrap_or((candidate, ""));
+
+            let url = rewrite_url(url, dir, manifest).unwrap_or_else(|| url.to_string());
+
+            match descriptor.trim() {
+                "" => url,
+                descriptor => format!("{url} {descriptor}"),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The fingerprinted version of `url` if it points to a renamed file, only the file name is
+/// changed so absolute, relative and query string urls keep working
+fn rewrite_url(url: &str, dir: &str, manifest: &Manifest) -> Option<String> {
+    let split = url.find(['?', '#']).unwrap_or(url.len());
+    let (path, suffix) = url.split_at(split);
+
+    let hashed = manifest.get(&logical_path(path, dir)?)?;
+    let hashed_name = hashed.rsplit('/').next()?;
+
+    let rewritten = match path.rsplit_once('/') {
+        Some((parent, _)) => format!("{parent}/{hashed_name}"),
+        None => hashed_name.to_string(),
+    };
+
+    Some(format!("{rewritten}{suffix}"))
+}
+
+/// The path of a url relative to the out dir, `dir` is the directory of the file it's in
+pub(crate) fn logical_path(url: &str, dir: &str) -> Option<String> {
+    if url.is_empty() || url.starts_with("//") || url.starts_with('#') || url.contains(':') {
+        return None;
+    }
+
+    let path = &url[..url.find(['?', '#']).unwrap_or(url.len())];
+
+    match path.strip_prefix('/') {
+        Some(absolute) => normalize(absolute),
+        None => normalize(&format!("{dir}/{path}")),
+    }
+}
+
+/// Resolves `.` and `..`, `None` when the path leaves the out dir
+pub(super) fn normalize(path: &str) -> Option<String> {
+    let mut segments: Vec<&str> = Vec::new();
+
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    Some(segments.join("/"))
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .is_some_and(|extension| extensions.contains(&extension.to_string_lossy().as_ref()))
+}
+
+fn url_path(out_dir: &Path, path: &Path) -> String {
+    path.strip_prefix(out_dir)
+        .unwrap_or(path)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn relative_dir(out_dir: &Path, path: &Path) -> String {
+    path.parent()
+        .map(|parent| url_path(out_dir, parent))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest() -> Manifest {
+        Manifest::from([("img/a.png".to_string(), "img/a.12345678.png".to_string())])
+    }
+
+    #[test]
+    fn keeps_attributes_that_point_to_nothing_hashed() {
+        let html = r#"<p style="content:'&amp;'"></p><img srcset="b.png?a=1&amp;b=2 2x">"#;
+
+        assert_eq!(rewrite_html(html, "", &manifest()).unwrap(), html);
+    }
+
+    #[test]
+    fn escapes_ampersands_in_rewritten_attributes() {
+        let html = r#"<img src="img/a.png?a=1&amp;b=2"><p style="background:url(/img/a.png);content:'&amp;'"></p>"#;
+
+        assert_eq!(
+            rewrite_html(html, "", &manifest()).unwrap(),
+            r#"<img src="img/a.12345678.png?a=1&amp;b=2"><p style="background:url(/img/a.12345678.png);content:'&amp;'"></p>"#
+        );
+    }
+
+    #[test]
+    fn rewrites_relative_urls_from_the_page_dir() {
+        let html = r#"<img srcset="../img/a.png 1x, ../img/b.png 2x">"#;
+
+        assert_eq!(
+            rewrite_html(html, "blog", &manifest()).unwrap(),
+            r#"<img srcset="../img/a.12345678.png 1x, ../img/b.png 2x">"#
+        );
+    }
+}
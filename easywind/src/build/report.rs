@@ -0,0 +1,103 @@
+use std::{io::Write, path::Path, path::PathBuf};
+
+use flate2::{write::GzEncoder, Compression};
+
+struct Row {
+    path: String,
+    size: u64,
+    gzip: Option<u64>,
+}
+
+/// Prints every file that was written with its size, and its gzip size for text files since
+/// that's what hosts send over the wire
+pub(super) fn print(out_dir: &Path, written: &[PathBuf]) {
+    let mut rows = written
+        .iter()
+        .filter_map(|path| row(out_dir, path))
+        .collect::<Vec<_>>();
+
+    rows.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let path_width = rows
+        .iter()
+        .map(|row| row.path.len())
+        .max()
+        .unwrap_or_default();
+
+    println!();
+    for row in &rows {
+        let gzip = row
+            .gzip
+            .map(|gzip| format!("gzip {}", format_size(gzip)))
+            .unwrap_or_default();
+
+        let line = format!(
+            "  {:path_width$}  {:>10}  {gzip}",
+            row.path,
+            format_size(row.size)
+        );
+        println!("{}", line.trim_end());
+    }
+
+    let total = rows.iter().map(|row| row.size).sum::<u64>();
+    let total_gzip = rows
+        .iter()
+        .map(|row| row.gzip.unwrap_or(row.size))
+        .sum::<u64>();
+
+    println!(
+        "\n  {} files, {} ({} with gzip) written to {}",
+        rows.len(),
+        format_size(total),
+        format_size(total_gzip),
+        out_dir.display()
+    );
+}
+
+fn row(out_dir: &Path, path: &Path) -> Option<Row> {
+    let size = path.metadata().ok()?.len();
+
+    let path_name = path
+        .strip_prefix(out_dir)
+        .unwrap_or(path)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let gzip = is_text(path)
+        .then(|| std::fs::read(path).ok())
+        .flatten()
+        .and_then(|contents| gzip_size(&contents));
+
+    Some(Row {
+        path: path_name,
+        size,
+        gzip,
+    })
+}
+
+fn is_text(path: &Path) -> bool {
+    mime_guess::from_path(path).first().is_some_and(|mime| {
+        mime.type_() == "text"
+            || matches!(
+                mime.essence_str(),
+                "application/javascript" | "application/json" | "image/svg+xml"
+            )
+    })
+}
+
+fn gzip_size(contents: &[u8]) -> Option<u64> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(contents).ok()?;
+
+    Some(encoder.finish().ok()?.len() as u64)
+}
+
+pub(super) fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{bytes} B"),
+        1024..1_048_576 => format!("{:.1} kB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
@@ -0,0 +1,482 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::Help;
+use eyre::{eyre, Context, Result};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::consts::{
+    CONFIG_FILE_NAME, DEFAULT_BUILD_DIR, DEFAULT_HOST, DEFAULT_INPUT, DEFAULT_OUTPUT, DEFAULT_PORT,
+    GLOBAL_CONFIG_PATH, TAILWIND_DOWNLOAD_URL,
+};
+use crate::server::{
+    access::{SymlinkPolicy, DEFAULT_HIDDEN},
+    cache::CacheMode,
+};
+use crate::tailwind::{installer::DownloadOptions, runner::RunnerKind};
+
+static ENV_PREFIX: &str = "EASYWIND_";
+static SECTIONS: &[&str] = &["server", "tailwind"];
+
+/// Options that can be set in `easywind.toml`, the global config file, `EASYWIND_*` env vars
+/// or CLI flags, every value is optional so that each source only sets what it knows about
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Root directory of the project, relative to the config file it is set in
+    pub root_dir: Option<PathBuf>,
+    /// Pages are html emails: css is inlined into `style` attributes when building and serving
+    pub email: Option<bool>,
+    pub server: ServerConfig,
+    pub tailwind: TailwindConfig,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Address to listen on, `0.0.0.0` or `::` to expose the server on the local network
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    /// Fail if the port is taken instead of using another one
+    pub strict_port: Option<bool>,
+    pub open: Option<bool>,
+    pub proxy: Option<String>,
+    /// Paths that are never served or listed, globs matched against each part of the path
+    pub hidden: Option<Vec<String>>,
+    /// Which symlinks are served: inside (the root dir), follow or deny
+    pub symlinks: Option<SymlinkPolicy>,
+    /// Caching headers sent to the browser: none, revalidate or immutable
+    pub cache: Option<CacheMode>,
+    /// Compress responses with gzip or brotli and serve `.br` and `.gz` siblings of files
+    pub compress: Option<bool>,
+    /// Page served for html requests that match no file, relative to the root dir, for single
+    /// page apps with client side routing
+    pub spa: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TailwindConfig {
+    /// Input css file, relative to the root dir
+    pub input: Option<PathBuf>,
+    /// Output css file, relative to the root dir
+    pub output: Option<PathBuf>,
+    /// Exact tailwind version to use, ex: 4.1.13
+    pub version: Option<String>,
+    /// Mirror to download the standalone cli from, instead of the github releases
+    pub download_url: Option<String>,
+    /// HTTP(S) proxy used to download the standalone cli
+    pub download_proxy: Option<String>,
+    /// Install the standalone cli even when its release has no checksums to verify it against,
+    /// ex: a mirror that doesn't host `sha256sums.txt`
+    pub skip_checksum: Option<bool>,
+    /// Which tailwind binary to run: auto, local, path, standalone or npx
+    pub runner: Option<RunnerKind>,
+}
+
+/// Where a config value was set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    Global(PathBuf),
+    Project(PathBuf),
+    Env(String),
+    Cli,
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::Global(path) => write!(f, "global config ({})", path.display()),
+            Self::Project(path) => write!(f, "project config ({})", path.display()),
+            Self::Env(var) => write!(f, "env var ({var})"),
+            Self::Cli => write!(f, "cli flag"),
+        }
+    }
+}
+
+/// The merged config, along with where each value came from
+#[derive(Debug, Clone)]
+pub struct ResolvedConfig {
+    pub config: Config,
+    pub project_file: Option<PathBuf>,
+    values: BTreeMap<String, (toml::Value, Source)>,
+}
+
+impl ResolvedConfig {
+    pub fn root_dir(&self) -> PathBuf {
+        self.config
+            .root_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+}
+
+impl Display for ResolvedConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let values = self
+            .values
+            .iter()
+            .map(|(key, (value, source))| (key, value.to_string(), source))
+            .collect::<Vec<_>>();
+
+        let key_width = values.iter().map(|(key, ..)| key.len()).max();
+        let value_width = values.iter().map(|(_, value, _)| value.len()).max();
+
+        for (key, value, source) in &values {
+            writeln!(
+                f,
+                "{key:key_width$} = {value:value_width$}  # {source}",
+                key_width = key_width.unwrap_or_default(),
+                value_width = value_width.unwrap_or_default(),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Config {
+    fn defaults() -> Self {
+        Self {
+            root_dir: None,
+            email: Some(false),
+            server: ServerConfig {
+                host: Some(DEFAULT_HOST.to_string()),
+                port: Some(DEFAULT_PORT),
+                strict_port: Some(false),
+                open: Some(false),
+                proxy: None,
+                hidden: Some(DEFAULT_HIDDEN.iter().map(ToString::to_string).collect()),
+                symlinks: Some(SymlinkPolicy::default()),
+                cache: Some(CacheMode::default()),
+                compress: Some(false),
+                spa: None,
+            },
+            tailwind: TailwindConfig {
+                input: Some(DEFAULT_INPUT.into()),
+                output: Some(DEFAULT_OUTPUT.into()),
+                version: None,
+                download_url: Some(TAILWIND_DOWNLOAD_URL.to_string()),
+                download_proxy: None,
+                skip_checksum: Some(false),
+                runner: Some(RunnerKind::Auto),
+            },
+        }
+    }
+
+    fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Unable to read config file: {}", path.display()))?;
+
+        toml::from_str(&contents)
+            .wrap_err_with(|| format!("Invalid config file: {}", path.display()))
+            .suggestion("Run `easywind config show` to see the available options")
+    }
+
+    fn to_table(&self) -> Result<toml::Table> {
+        toml::Table::try_from(self).wrap_err("Unable to serialize config")
+    }
+}
+
+/// Merges every config source, `cli` is the config built from the CLI flags and wins over all
+/// other sources. The project config is found by walking up from the root dir
+pub fn resolve(cli: Config) -> Result<ResolvedConfig> {
+    let start_dir = std::path::absolute(cli.root_dir.as_deref().unwrap_or(Path::new(".")))?;
+    let mut values = BTreeMap::new();
+
+    let defaults = Config {
+        root_dir: Some(start_dir.clone()),
+        ..Config::defaults()
+    };
+    merge(&mut values, &defaults, Source::Default)?;
+
+    if GLOBAL_CONFIG_PATH.exists() {
+        let global = Config::load(&GLOBAL_CONFIG_PATH)?;
+        merge(
+            &mut values,
+            &global,
+            Source::Global(GLOBAL_CONFIG_PATH.clone()),
+        )?;
+    }
+
+    let project_file = find_project_file(&start_dir);
+
+    if let Some(path) = &project_file {
+        debug!("Using project config: {}", path.display());
+
+        let mut project = Config::load(path)?;
+        let project_dir = path.parent().unwrap_or(Path::new("."));
+
+        project.root_dir = Some(match project.root_dir {
+            Some(root_dir) => project_dir.join(root_dir),
+            None => project_dir.to_path_buf(),
+        });
+
+        merge(&mut values, &project, Source::Project(path.clone()))?;
+    }
+
+    for (var, key, value) in env_values() {
+        values.insert(key, (value, Source::Env(var)));
+    }
+
+    // paths passed on the command line are relative to where easywind is run
+    let mut cli = cli;
+    cli.root_dir = cli.root_dir.map(std::path::absolute).transpose()?;
+    cli.tailwind.input = cli.tailwind.input.map(std::path::absolute).transpose()?;
+    cli.tailwind.output = cli.tailwind.output.map(std::path::absolute).transpose()?;
+
+    merge(&mut values, &cli, Source::Cli)?;
+
+    let config = unflatten(&values)
+        .try_into()
+        .wrap_err("Unable to merge config")?;
+
+    Ok(ResolvedConfig {
+        config,
+        project_file,
+        values,
+    })
+}
+
+/// Sets a single key in a config file, ex: `tailwind.version`, the rest of the file is left as is
+pub fn set_value(path: &Path, key: &str, value: &str) -> Result<()> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(error) => {
+            return Err(error)
+                .wrap_err_with(|| format!("Unable to read config file: {}", path.display()))
+        }
+    };
+
+    let mut document = contents
+        .parse::<toml_edit::DocumentMut>()
+        .wrap_err_with(|| format!("Invalid config file: {}", path.display()))?;
+
+    let mut item = document.as_item_mut();
+    let mut parts = key.split('.').peekable();
+
+    while let Some(part) = parts.next() {
+        // create missing sections as `[section]` tables, not inline tables
+        if parts.peek().is_some() && item.get(part).is_none() {
+            item[part] = toml_edit::table();
+        }
+
+        item = &mut item[part];
+    }
+
+    *item = toml_edit::value(value);
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::write(path, document.to_string())
+        .wrap_err_with(|| format!("Unable to write config file: {}", path.display()))
+}
+
+fn find_project_file(start_dir: &Path) -> Option<PathBuf> {
+    start_dir
+        .ancestors()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+fn merge(
+    values: &mut BTreeMap<String, (toml::Value, Source)>,
+    config: &Config,
+    source: Source,
+) -> Result<()> {
+    let mut flat = BTreeMap::new();
+    flatten(config.to_table()?, "", &mut flat);
+
+    for (key, value) in flat {
+        values.insert(key, (value, source.clone()));
+    }
+
+    Ok(())
+}
+
+fn flatten(table: toml::Table, prefix: &str, out: &mut BTreeMap<String, toml::Value>) {
+    for (key, value) in table {
+        let key = format!("{prefix}{key}");
+
+        match value {
+            toml::Value::Table(table) => flatten(table, &format!("{key}."), out),
+            value => {
+                out.insert(key, value);
+            }
+        }
+    }
+}
+
+fn unflatten(values: &BTreeMap<String, (toml::Value, Source)>) -> toml::Value {
+    let mut root = toml::Table::new();
+
+    for (key, (value, _)) in values {
+        let mut table = &mut root;
+        let mut parts = key.split('.').peekable();
+
+        while let Some(part) = parts.next() {
+            if parts.peek().is_none() {
+                table.insert(part.to_string(), value.clone());
+                break;
+            }
+
+            table = table
+                .entry(part)
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                .as_table_mut()
+                .expect("config sections are always tables");
+        }
+    }
+
+    toml::Value::Table(root)
+}
+
+// env vars map to config keys, ex: `EASYWIND_SERVER_PORT` sets `server.port`
+fn env_values() -> Vec<(String, String, toml::Value)> {
+    std::env::vars()
+        .filter_map(|(var, raw)| {
+            let name = var.strip_prefix(ENV_PREFIX)?.to_lowercase();
+
+            let key = SECTIONS
+                .iter()
+                .find_map(|section| {
+                    let field = name.strip_prefix(section)?.strip_prefix('_')?;
+                    Some(format!("{section}.{field}"))
+                })
+                .unwrap_or(name);
+
+            match parse_env_value(&key, &raw) {
+                Ok(value) => Some((var, key, value)),
+                Err(error) => {
+                    warn!("Ignoring env var {var}: {error}");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+// env vars are untyped, try it as a toml value first (numbers, bools) and fall back to a string
+fn parse_env_value(key: &str, raw: &str) -> Result<toml::Value> {
+    let parsed = toml::from_str::<toml::Table>(&format!("value = {raw}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"));
+
+    let candidates = parsed
+        .into_iter()
+        .chain(std::iter::once(toml::Value::String(raw.to_string())));
+
+    for value in candidates {
+        let single = BTreeMap::from([(key.to_string(), (value.clone(), Source::Cli))]);
+
+        if unflatten(&single).try_into::<Config>().is_ok() {
+            return Ok(value);
+        }
+    }
+
+    Err(eyre!(
+        "{key} is not a valid config option or has an invalid value"
+    ))
+}
+
+impl From<&TailwindConfig> for DownloadOptions {
+    fn from(tailwind: &TailwindConfig) -> Self {
+        let defaults = Self::default();
+
+        Self {
+            base_url: tailwind.download_url.clone().unwrap_or(defaults.base_url),
+            proxy: tailwind.download_proxy.clone(),
+            skip_checksum: tailwind.skip_checksum.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<ResolvedConfig> for crate::start::StartArgs {
+    fn from(resolved: ResolvedConfig) -> Self {
+        let root_dir = resolved.root_dir();
+        let Config {
+            server,
+            tailwind,
+            email,
+            ..
+        } = resolved.config;
+
+        Self {
+            tailwind_download: (&tailwind).into(),
+            host: server.host.unwrap_or_else(|| DEFAULT_HOST.to_string()),
+            port: server.port.unwrap_or(DEFAULT_PORT),
+            strict_port: server.strict_port.unwrap_or_default(),
+            open: server.open.unwrap_or_default(),
+            input: tailwind.input.map(|input| root_dir.join(input)),
+            output: tailwind.output.map(|output| root_dir.join(output)),
+            proxy: server.proxy,
+            hidden: server
+                .hidden
+                .unwrap_or_else(|| DEFAULT_HIDDEN.iter().map(ToString::to_string).collect()),
+            symlinks: server.symlinks.unwrap_or_default(),
+            cache: server.cache.unwrap_or_default(),
+            compress: server.compress.unwrap_or_default(),
+            spa: server.spa,
+            email: email.unwrap_or_default(),
+            tailwind_version: tailwind.version,
+            tailwind_runner: tailwind.runner.unwrap_or_default(),
+            root_dir,
+        }
+    }
+}
+
+impl From<ResolvedConfig> for crate::server::ServerArgs {
+    fn from(resolved: ResolvedConfig) -> Self {
+        crate::start::StartArgs::from(resolved).into()
+    }
+}
+
+impl From<ResolvedConfig> for crate::build::BuildArgs {
+    fn from(resolved: ResolvedConfig) -> Self {
+        let root_dir = resolved.root_dir();
+        let hidden = resolved
+            .config
+            .server
+            .hidden
+            .clone()
+            .unwrap_or_else(|| DEFAULT_HIDDEN.iter().map(ToString::to_string).collect());
+        let email = resolved.config.email.unwrap_or_default();
+
+        Self {
+            out_dir: root_dir.join(DEFAULT_BUILD_DIR),
+            tailwind: resolved.into(),
+            hidden,
+            fingerprint: true,
+            email,
+            root_dir,
+        }
+    }
+}
+
+impl From<ResolvedConfig> for crate::tailwind::TailwindArgs {
+    fn from(resolved: ResolvedConfig) -> Self {
+        let root_dir = resolved.root_dir();
+        let tailwind = resolved.config.tailwind;
+
+        Self {
+            download: (&tailwind).into(),
+            input: root_dir.join(tailwind.input.unwrap_or_else(|| DEFAULT_INPUT.into())),
+            output: root_dir.join(tailwind.output.unwrap_or_else(|| DEFAULT_OUTPUT.into())),
+            root_dir,
+            watch: false,
+            minify: false,
+            version: tailwind.version,
+            runner: tailwind.runner.unwrap_or_default(),
+            reloader: None,
+        }
+    }
+}
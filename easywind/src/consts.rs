@@ -3,7 +3,19 @@ use std::path::PathBuf;
 use etcetera::{AppStrategy, AppStrategyArgs};
 use once_cell::sync::Lazy;
 
-pub static LATEST_TAILWIND_VERSION: &str = "3.3.3";
+pub static LATEST_TAILWIND_VERSION: &str = "4.1.13";
+pub static LATEST_TAILWIND_V3_VERSION: &str = "3.4.17";
+
+pub static DEFAULT_HOST: &str = "127.0.0.1";
+pub static DEFAULT_PORT: u16 = 3500;
+pub static DEFAULT_INPUT: &str = "src/app.css";
+pub static DEFAULT_OUTPUT: &str = "dist/app.css";
+pub static DEFAULT_BUILD_DIR: &str = "build";
+
+pub static TAILWIND_DOWNLOAD_URL: &str =
+    "https://github.com/tailwindlabs/tailwindcss/releases/download";
+
+pub static CONFIG_FILE_NAME: &str = "easywind.toml";
 
 pub static CONFIG_DIR: Lazy<PathBuf> = Lazy::new(|| {
     etcetera::app_strategy::choose_app_strategy(AppStrategyArgs {
@@ -15,11 +27,19 @@ pub static CONFIG_DIR: Lazy<PathBuf> = Lazy::new(|| {
     .config_dir()
 });
 
-pub static TAILWIND_BIN_DIR: Lazy<PathBuf> = Lazy::new(|| {
-    CONFIG_DIR
-        .join("bin")
-        .join("tailwindcss")
-        .join(LATEST_TAILWIND_VERSION)
-});
+pub static GLOBAL_CONFIG_PATH: Lazy<PathBuf> = Lazy::new(|| CONFIG_DIR.join("config.toml"));
+
+/// The files each build wrote, kept here instead of the out dir so they're never deployed
+pub static BUILD_RECORDS_DIR: Lazy<PathBuf> = Lazy::new(|| CONFIG_DIR.join("builds"));
+
+pub static TAILWIND_BIN_ROOT: Lazy<PathBuf> =
+    Lazy::new(|| CONFIG_DIR.join("bin").join("tailwindcss"));
+
+pub fn tailwind_bin_dir(version: &str) -> PathBuf {
+    TAILWIND_BIN_ROOT.join(version)
+}
 
-pub static TAILWIND_CLI_PATH: Lazy<PathBuf> = Lazy::new(|| TAILWIND_BIN_DIR.join("tailwindcss"));
+pub fn tailwind_cli_path(version: &str) -> PathBuf {
+    // windows only runs files with an .exe extension
+    tailwind_bin_dir(version).join(format!("tailwindcss{}", std::env::consts::EXE_SUFFIX))
+}
@@ -0,0 +1,504 @@
+mod stylesheet;
+mod support;
+mod values;
+
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::BTreeSet,
+    rc::Rc,
+};
+
+use eyre::{eyre, Result};
+use lol_html::{
+    element,
+    html_content::{ContentType, Element, EndTag},
+    rewrite_str, ElementContentHandlers, EndTagHandler, RewriteStrSettings, Selector,
+};
+
+use self::{
+    stylesheet::{split_selectors, Declaration, Item},
+    values::Variables,
+};
+use crate::build::fingerprint::{attribute, logical_path};
+
+// tailwind v4 layers that only hold variables and the preflight reset, neither belongs in an
+// email, the variables are resolved instead
+static SKIPPED_LAYERS: &[&str] = &["theme", "base", "properties"];
+
+/// Compiled css split into rules that are inlined into `style` attributes and the ones that
+/// can only work from a `<style>` block, like media queries and `:hover`
+#[derive(Debug, Clone, Default)]
+pub struct EmailCss {
+    inline: Vec<InlineRule>,
+    /// Css for the `<style>` block in the head
+    head: String,
+    /// Problems found while parsing, ex: a variable without a value
+    warnings: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone)]
+struct InlineRule {
+    selector: String,
+    /// (ids, classes, types), rules with a higher one win like in the browser
+    specificity: (u32, u32, u32),
+    declarations: Vec<Declaration>,
+}
+
+/// Html with the css inlined, and everything in it that won't work in most email clients
+#[derive(Debug, Clone)]
+pub struct Inlined {
+    pub html: String,
+    pub warnings: BTreeSet<String>,
+}
+
+impl EmailCss {
+    pub fn parse(css: &str) -> Self {
+        let items = stylesheet::parse(css);
+        let variables = variables(&items);
+
+        let mut email_css = Self::default();
+        let head = email_css.split(&items, &variables, None, false);
+        email_css.head = stylesheet::to_css(&head);
+
+        email_css
+    }
+
+    // collects the rules that can be inlined and returns the ones that have to stay in the head
+    fn split(
+        &mut self,
+        items: &[Item],
+        variables: &Variables,
+        layer: Option<&str>,
+        conditional: bool,
+    ) -> Vec<Item> {
+        let mut head = Vec::new();
+
+        for item in items {
+            match item {
+                Item::Rule {
+                    selector,
+                    declarations,
+                } => {
+                    if layer.is_some_and(|layer| SKIPPED_LAYERS.contains(&layer)) {
+                        continue;
+                    }
+
+                    let mut declarations = self.resolve(selector, declarations, variables);
+
+                    if declarations.is_empty() {
+                        continue;
+                    }
+
+                    // inlined styles win over the `<style>` block, so a media query could never
+                    // override them without `!important`
+                    if conditional {
+                        for declaration in &mut declarations {
+                            declaration.important = true;
+                        }
+                    }
+
+                    let (inline, rest) = if conditional {
+                        (vec![], split_selectors(selector))
+                    } else {
+                        split_selectors(selector)
+                            .into_iter()
+                            .partition(|selector| is_inlinable(selector))
+                    };
+
+                    for selector in inline {
+                        self.inline.push(InlineRule {
+                            specificity: specificity(&selector),
+                            selector,
+                            declarations: declarations.clone(),
+                        });
+                    }
+
+                    if !rest.is_empty() {
+                        head.push(Item::Rule {
+                            selector: rest.join(","),
+                            declarations,
+                        });
+                    }
+                }
+
+                // email clients don't know about layers, their rules are used in order
+                Item::Block { prelude, items } if prelude.starts_with("@layer") => {
+                    let name = prelude.trim_start_matches("@layer").trim();
+                    head.extend(self.split(items, variables, Some(name), conditional));
+                }
+
+                Item::Block { prelude, items } => {
+                    let items = self.split(items, variables, layer, true);
+
+                    if !items.is_empty() {
+                        head.push(Item::Block {
+                            prelude: prelude.clone(),
+                            items,
+                        });
+                    }
+                }
+
+                // `@property` only declares variables, and `@layer a, b;` only orders layers
+                Item::AtRule { prelude, .. }
+                    if prelude.starts_with("@property") || prelude.starts_with("@layer") => {}
+
+                Item::AtRule { .. } => head.push(item.clone()),
+            }
+        }
+
+        head
+    }
+
+    // resolves every value and logical property, custom properties are dropped since email
+    // clients don't support them
+    fn resolve(
+        &mut self,
+        selector: &str,
+        declarations: &[Declaration],
+        variables: &Variables,
+    ) -> Vec<Declaration> {
+        declarations
+            .iter()
+            .filter(|declaration| !declaration.is_custom_property())
+            .filter_map(|declaration| {
+                let Some(value) = values::resolve(&declaration.value, variables) else {
+                    self.warnings.insert(format!(
+                        "`{selector}`: `{}` uses a variable without a value, it was left out",
+                        declaration.name
+                    ));
+                    return None;
+                };
+
+                Some(Declaration {
+                    value,
+                    ..declaration.clone()
+                })
+            })
+            .flat_map(support::physical)
+            .collect()
+    }
+}
+
+/// Inlines the css into the `style` attribute of every element it applies to. The project's
+/// stylesheet link is replaced with a `<style>` block for the rules that can't be inlined,
+/// `page_url` is where the page is served, ex: `/blog/`, relative links are resolved from it
+pub fn inline(
+    html: &str,
+    css: &EmailCss,
+    stylesheet: Option<&str>,
+    page_url: &str,
+) -> Result<Inlined> {
+    // each element's matching rules are collected by the handlers before `*` applies them
+    let matched = RefCell::new(Vec::<usize>::new());
+    let warnings = RefCell::new(css.warnings.clone());
+    // end tag handlers outlive the rewrite, so this is shared instead of borrowed
+    let head_written = Rc::new(Cell::new(false));
+
+    // `dist/app.css` from the root page is the same file as `/dist/app.css`
+    let page_dir = page_url
+        .trim_start_matches('/')
+        .rsplit_once('/')
+        .map(|(dir, _)| dir)
+        .unwrap_or_default();
+
+    let style_block = match css.head.is_empty() {
+        true => String::new(),
+        false => format!("<style>{}</style>", css.head),
+    };
+
+    let mut handlers = css
+        .inline
+        .iter()
+        .enumerate()
+        .filter_map(|(index, rule)| {
+            let selector = rule.selector.parse::<Selector>().ok()?;
+            let matched = &matched;
+
+            Some((
+                Cow::Owned(selector),
+                ElementContentHandlers::default().element(move |_: &mut Element| {
+                    matched.borrow_mut().push(index);
+                    Ok(())
+                }),
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    handlers.push(element!("*", |el| {
+        let mut rules = std::mem::take(&mut *matched.borrow_mut());
+
+        if rules.is_empty() {
+            return Ok(());
+        }
+
+        // later rules win over earlier ones with the same specificity
+        rules.sort_by_key(|index| (css.inline[*index].specificity, *index));
+
+        let mut style = Vec::<Declaration>::new();
+        let mut important = Vec::<Declaration>::new();
+
+        for rule in rules.iter().map(|index| &css.inline[*index]) {
+            for declaration in &rule.declarations {
+                if let Some(reason) = support::unsupported(declaration) {
+                    warnings
+                        .borrow_mut()
+                        .insert(format!("`{}`: {reason}", rule.selector));
+                }
+
+                match declaration.important {
+                    true => set(&mut important, declaration.clone()),
+                    false => set(&mut style, declaration.clone()),
+                }
+            }
+        }
+
+        // the element's own style wins over rules, `!important` wins over both
+        let existing = el.get_attribute("style").unwrap_or_default();
+        for declaration in stylesheet::parse_declarations(&existing) {
+            set(&mut style, declaration);
+        }
+
+        for declaration in important {
+            set(&mut style, declaration);
+        }
+
+        el.set_attribute("style", &stylesheet::declarations_to_css(&style))?;
+        Ok(())
+    }));
+
+    handlers.push(element!("link[rel~=stylesheet][href]", |el| {
+        let href = attribute(el, "href").unwrap_or_default();
+        let logical = logical_path(&href, page_dir);
+
+        if stylesheet.is_some_and(|stylesheet| {
+            logical.as_deref() == Some(stylesheet.trim_start_matches('/'))
+        }) {
+            el.replace(&style_block, ContentType::Html);
+            head_written.set(true);
+        }
+
+        Ok(())
+    }));
+
+    // pages that don't link the stylesheet still get the rules that can't be inlined
+    handlers.push(element!("head", |el| {
+        let head_written = head_written.clone();
+        let style_block = style_block.clone();
+
+        if let Some(handlers) = el.end_tag_handlers() {
+            let handler: EndTagHandler<'static> = Box::new(move |end: &mut EndTag| {
+                if !head_written.get() {
+                    end.before(&style_block, ContentType::Html);
+                }
+
+                Ok(())
+            });
+
+            handlers.push(handler);
+        }
+
+        Ok(())
+    }));
+
+    let html = rewrite_str(
+        html,
+        RewriteStrSettings {
+            element_content_handlers: handlers,
+            ..RewriteStrSettings::new()
+        },
+    )
+    .map_err(|error| eyre!("Unable to inline css: {error}"))?;
+
+    Ok(Inlined {
+        html,
+        warnings: warnings.into_inner(),
+    })
+}
+
+// replaces an earlier declaration of the same property so it keeps its place
+fn set(declarations: &mut Vec<Declaration>, declaration: Declaration) {
+    declarations.retain(|existing| existing.name != declaration.name);
+    declarations.push(declaration);
+}
+
+// custom property values from everywhere but media queries, `@property` initial values
+// fill in the ones tailwind sets to `initial`
+fn variables(items: &[Item]) -> Variables {
+    fn collect(items: &[Item], variables: &mut Variables, initial: &mut Variables) {
+        for item in items {
+            match item {
+                Item::Rule { declarations, .. } => {
+                    for declaration in declarations.iter().filter(|d| d.is_custom_property()) {
+                        variables.insert(declaration.name.clone(), declaration.value.clone());
+                    }
+                }
+                Item::Block { prelude, .. } if prelude.starts_with("@media") => {}
+                Item::Block { items, .. } => collect(items, variables, initial),
+                Item::AtRule {
+                    prelude,
+                    body: Some(body),
+                } if prelude.starts_with("@property") => {
+                    let name = prelude.trim_start_matches("@property").trim();
+
+                    if let Some(value) = stylesheet::parse_declarations(body)
+                        .into_iter()
+                        .find(|declaration| declaration.name == "initial-value")
+                    {
+                        initial.insert(name.to_string(), value.value);
+                    }
+                }
+                Item::AtRule { .. } => {}
+            }
+        }
+    }
+
+    let mut variables = Variables::new();
+    let mut initial = Variables::new();
+    collect(items, &mut variables, &mut initial);
+
+    for (name, value) in initial {
+        match variables.get(&name).map(String::as_str) {
+            None | Some("initial") => {
+                variables.insert(name, value);
+            }
+            Some(_) => {}
+        }
+    }
+
+    variables
+}
+
+// selectors that apply to an element on their own, `:hover` or `::before` need a browser and
+// `*` would put the same styles on every element
+fn is_inlinable(selector: &str) -> bool {
+    selector != "*" && selector.parse::<Selector>().is_ok()
+}
+
+fn specificity(selector: &str) -> (u32, u32, u32) {
+    let mut specificity = (0, 0, 0);
+    let mut chars = selector.char_indices().peekable();
+    let mut at_start = true;
+
+    while let Some((index, c)) = chars.next() {
+        let rest = &selector[index + c.len_utf8()..];
+
+        match c {
+            // escaped characters are part of a name, ex: `.sm\:px-4`
+            '\\' => {
+                chars.next();
+            }
+            '#' => specificity.0 += 1,
+            '.' | '[' => specificity.1 += 1,
+            // `:where()` adds nothing, its arguments are skipped
+            ':' if rest.starts_with("where(") => {
+                let mut depth = 0;
+
+                for (_, c) in chars.by_ref() {
+                    match c {
+                        '(' => depth += 1,
+                        ')' if depth == 1 => break,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                }
+            }
+            // `:not()` and `:is()` count as their arguments, not as a pseudo-class
+            ':' if rest.starts_with("not(") || rest.starts_with("is(") => {}
+            // pseudo-elements count like a type, ex: `::before`
+            ':' if rest.starts_with(':') => {
+                chars.next();
+                specificity.2 += 1;
+            }
+            ':' => specificity.1 += 1,
+            c if at_start && c.is_ascii_alphabetic() => specificity.2 += 1,
+            _ => {}
+        }
+
+        at_start = matches!(c, ' ' | '>' | '+' | '~' | '(');
+    }
+
+    specificity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inline_html(html: &str, css: &str, page_url: &str) -> String {
+        inline(html, &EmailCss::parse(css), Some("/dist/app.css"), page_url)
+            .unwrap()
+            .html
+    }
+
+    #[test]
+    fn orders_rules_by_specificity() {
+        assert!(specificity("#a") > specificity(".a.b.c"));
+        assert!(specificity(".a") > specificity("p span"));
+        assert_eq!(specificity("a:hover"), (0, 1, 1));
+        assert_eq!(specificity("p::before"), (0, 0, 2));
+        assert_eq!(specificity(".sm\\:px-4"), (0, 1, 0));
+    }
+
+    #[test]
+    fn counts_the_arguments_of_not_and_is() {
+        assert_eq!(specificity(":not(.a)"), (0, 1, 0));
+        assert_eq!(specificity("p:is(#a)"), (1, 0, 1));
+        assert_eq!(specificity(":where(#a .b) p"), (0, 0, 1));
+    }
+
+    #[test]
+    fn more_specific_rules_win_over_later_ones() {
+        let html = inline_html(
+            r#"<p class="a" id="b">hi</p>"#,
+            "#b { color: red } .a { color: blue; margin: 0 }",
+            "/",
+        );
+
+        assert_eq!(
+            html,
+            r#"<p class="a" id="b" style="margin:0;color:red">hi</p>"#
+        );
+    }
+
+    #[test]
+    fn existing_styles_win_over_rules() {
+        let html = inline_html(
+            r#"<p class="a" style="color: green">hi</p>"#,
+            ".a { color: red; padding: 1px }",
+            "/",
+        );
+
+        assert_eq!(
+            html,
+            r#"<p class="a" style="padding:1px;color:green">hi</p>"#
+        );
+    }
+
+    #[test]
+    fn keeps_media_queries_in_the_head() {
+        let html = inline_html(
+            r#"<html><head></head><body><p class="a">hi</p></body></html>"#,
+            ".a { color: red } @media (width >= 40rem) { .sm\\:a { color: blue } }",
+            "/",
+        );
+
+        assert!(html.contains(r#"<p class="a" style="color:red">"#));
+        assert!(html.contains(
+            "<style>@media (width >= 40rem){.sm\\:a{color:blue !important}}</style></head>"
+        ));
+    }
+
+    #[test]
+    fn replaces_a_relative_stylesheet_link() {
+        let html = inline_html(
+            r#"<html><head><link rel="stylesheet" href="../dist/app.css?v=1#x"></head></html>"#,
+            "@media print { .a { color: red } }",
+            "/blog/post.html",
+        );
+
+        assert_eq!(html.matches("<style>").count(), 1);
+        assert!(!html.contains("<link"));
+        assert!(html.contains("<head><style>"));
+    }
+}
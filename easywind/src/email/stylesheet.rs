@@ -0,0 +1,504 @@
+// a small css parser, enough to split tailwind's output into rules that can be inlined and
+// the ones that have to stay in a `<style>` block
+
+/// A top level item of a stylesheet, or one nested in an at-rule
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    /// `.px-4{padding-inline:1rem}`
+    Rule {
+        selector: String,
+        declarations: Vec<Declaration>,
+    },
+    /// At-rules that contain other rules, ex: `@media (width >= 40rem){...}` or `@layer base{...}`
+    Block { prelude: String, items: Vec<Item> },
+    /// Any other at-rule, kept as written, ex: `@font-face{...}` or `@property --x{...}`
+    AtRule {
+        prelude: String,
+        body: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    pub name: String,
+    pub value: String,
+    pub important: bool,
+}
+
+impl Declaration {
+    pub fn is_custom_property(&self) -> bool {
+        self.name.starts_with("--")
+    }
+}
+
+// at-rules whose body is a list of rules, every other at-rule is kept as is
+static GROUPING_AT_RULES: &[&str] = &[
+    "@media",
+    "@supports",
+    "@layer",
+    "@container",
+    "@scope",
+    "@starting-style",
+];
+
+pub fn parse(css: &str) -> Vec<Item> {
+    let css = strip_comments(css);
+    parse_items(&css)
+}
+
+fn parse_items(css: &str) -> Vec<Item> {
+    let mut items = Vec::new();
+    let mut rest = css;
+
+    loop {
+        rest = rest.trim_start();
+
+        if rest.is_empty() {
+            return items;
+        }
+
+        let Some((end, delimiter)) = find_delimiter(rest, &['{', ';']) else {
+            return items;
+        };
+
+        let prelude = rest[..end].trim().to_string();
+
+        if delimiter == ';' {
+            // statements like `@charset` or `@layer theme, base;`
+            if prelude.starts_with('@') {
+                items.push(Item::AtRule {
+                    prelude,
+                    body: None,
+                });
+            }
+
+            rest = &rest[end + 1..];
+            continue;
+        }
+
+        let body_start = end + 1;
+        let body_end = matching_brace(rest, end).unwrap_or(rest.len());
+        let body = &rest[body_start..body_end.min(rest.len())];
+
+        let at_rule = prelude
+            .split(|c: char| c.is_whitespace() || c == '(')
+            .next()
+            .unwrap_or_default();
+
+        let item = if GROUPING_AT_RULES.contains(&at_rule) {
+            Item::Block {
+                prelude,
+                items: parse_items(body),
+            }
+        } else if prelude.starts_with('@') {
+            Item::AtRule {
+                prelude,
+                body: Some(body.to_string()),
+            }
+        } else {
+            items.extend(parse_rule(&prelude, body));
+            rest = rest.get(body_end + 1..).unwrap_or_default();
+            continue;
+        };
+
+        items.push(item);
+        rest = rest.get(body_end + 1..).unwrap_or_default();
+    }
+}
+
+// tailwind v4 keeps nesting for variants, ex: `.sm\:p-8{@media (width >= 40rem){...}}`, they're
+// flattened into a rule inside the at-rule or a rule with the full selector
+fn parse_rule(selector: &str, body: &str) -> Vec<Item> {
+    let mut declarations = Vec::new();
+    let mut nested = Vec::new();
+    let mut rest = body;
+
+    while !rest.trim().is_empty() {
+        let (end, delimiter) = find_delimiter(rest, &[';', '{']).unwrap_or((rest.len(), ';'));
+
+        if delimiter == ';' {
+            declarations.extend(parse_declaration(&rest[..end]));
+            rest = rest.get(end + 1..).unwrap_or_default();
+            continue;
+        }
+
+        let prelude = rest[..end].trim();
+        let body_end = matching_brace(rest, end).unwrap_or(rest.len());
+        let body = &rest[end + 1..body_end.min(rest.len())];
+
+        let at_rule = prelude
+            .split(|c: char| c.is_whitespace() || c == '(')
+            .next()
+            .unwrap_or_default();
+
+        if GROUPING_AT_RULES.contains(&at_rule) {
+            nested.push(Item::Block {
+                prelude: prelude.to_string(),
+                items: parse_rule(selector, body),
+            });
+        } else if !prelude.starts_with('@') {
+            nested.extend(parse_rule(&nest_selector(selector, prelude), body));
+        }
+
+        rest = rest.get(body_end + 1..).unwrap_or_default();
+    }
+
+    let rule = (!declarations.is_empty()).then(|| Item::Rule {
+        selector: selector.to_string(),
+        declarations,
+    });
+
+    rule.into_iter().chain(nested).collect()
+}
+
+// `.a` and `&:hover` -> `.a:hover`, without a `&` the nested selector is a descendant
+fn nest_selector(parent: &str, nested: &str) -> String {
+    let parents = split_selectors(parent);
+
+    split_selectors(nested)
+        .iter()
+        .flat_map(|nested| {
+            parents
+                .iter()
+                .map(move |parent| match nested.contains('&') {
+                    true => nested.replace('&', parent),
+                    false => format!("{parent} {nested}"),
+                })
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// `.a, .b > p` -> [`.a`, `.b > p`]
+pub fn split_selectors(selector: &str) -> Vec<String> {
+    let mut selectors = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (index, c) in selector.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                selectors.push(selector[start..index].trim().to_string());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+
+    selectors.push(selector[start..].trim().to_string());
+    selectors.retain(|selector| !selector.is_empty());
+    selectors
+}
+
+/// Declarations of a rule or a `style` attribute, nested rules are skipped
+pub fn parse_declarations(body: &str) -> Vec<Declaration> {
+    let mut declarations = Vec::new();
+    let mut rest = body;
+
+    while !rest.trim().is_empty() {
+        let (end, delimiter) = find_delimiter(rest, &[';', '{']).unwrap_or((rest.len(), ';'));
+
+        if delimiter == '{' {
+            let body_end = matching_brace(rest, end).unwrap_or(rest.len());
+            rest = rest.get(body_end + 1..).unwrap_or_default();
+            continue;
+        }
+
+        if let Some(declaration) = parse_declaration(&rest[..end]) {
+            declarations.push(declaration);
+        }
+
+        rest = rest.get(end + 1..).unwrap_or_default();
+    }
+
+    declarations
+}
+
+fn parse_declaration(declaration: &str) -> Option<Declaration> {
+    let (name, value) = declaration.split_once(':')?;
+    let name = name.trim();
+    let value = value.trim();
+
+    if name.is_empty() {
+        return None;
+    }
+
+    let (value, important) = match value.strip_suffix("!important") {
+        Some(value) => (value.trim_end(), true),
+        None => (value, false),
+    };
+
+    Some(Declaration {
+        // custom properties are case sensitive
+        name: if name.starts_with("--") {
+            name.to_string()
+        } else {
+            name.to_ascii_lowercase()
+        },
+        value: value.to_string(),
+        important,
+    })
+}
+
+/// Writes the items back out as css
+pub fn to_css(items: &[Item]) -> String {
+    items.iter().map(item_to_css).collect::<Vec<_>>().join("")
+}
+
+fn item_to_css(item: &Item) -> String {
+    match item {
+        Item::Rule {
+            selector,
+            declarations,
+        } => format!("{selector}{{{}}}", declarations_to_css(declarations)),
+        Item::Block { prelude, items } => format!("{prelude}{{{}}}", to_css(items)),
+        Item::AtRule {
+            prelude,
+            body: Some(body),
+        } => format!("{prelude}{{{body}}}"),
+        Item::AtRule {
+            prelude,
+            body: None,
+        } => format!("{prelude};"),
+    }
+}
+
+pub fn declarations_to_css(declarations: &[Declaration]) -> String {
+    declarations
+        .iter()
+        .map(|declaration| {
+            let important = if declaration.important {
+                " !important"
+            } else {
+                ""
+            };
+
+            format!("{}:{}{important}", declaration.name, declaration.value)
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+// the position of the first delimiter outside of strings, parentheses and brackets
+fn find_delimiter(css: &str, delimiters: &[char]) -> Option<(usize, char)> {
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut escaped = false;
+
+    for (index, c) in css.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+
+        match (c, quote) {
+            ('\\', _) => escaped = true,
+            (c, Some(open)) if c == open => quote = None,
+            (_, Some(_)) => {}
+            ('"' | '\'', None) => quote = Some(c),
+            ('(' | '[', None) => depth += 1,
+            (')' | ']', None) => depth = depth.saturating_sub(1),
+            (c, None) if depth == 0 && delimiters.contains(&c) => return Some((index, c)),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+// the `}` that closes the `{` at `open`
+fn matching_brace(css: &str, open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut escaped = false;
+
+    for (index, c) in css[open..].char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+
+        match (c, quote) {
+            ('\\', _) => escaped = true,
+            (c, Some(open)) if c == open => quote = None,
+            (_, Some(_)) => {}
+            ('"' | '\'', None) => quote = Some(c),
+            ('{', None) => depth += 1,
+            ('}', None) => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(open + index);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut stripped = String::with_capacity(css.len());
+    let mut rest = css;
+
+    while let Some(start) = rest.find("/*") {
+        stripped.push_str(&rest[..start]);
+
+        match rest[start + 2..].find("*/") {
+            Some(end) => rest = &rest[start + 2 + end + 2..],
+            None => return stripped,
+        }
+    }
+
+    stripped.push_str(rest);
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn declaration(name: &str, value: &str) -> Declaration {
+        Declaration {
+            name: name.to_string(),
+            value: value.to_string(),
+            important: false,
+        }
+    }
+
+    fn rule(selector: &str, declarations: Vec<Declaration>) -> Item {
+        Item::Rule {
+            selector: selector.to_string(),
+            declarations,
+        }
+    }
+
+    fn block(prelude: &str, items: Vec<Item>) -> Item {
+        Item::Block {
+            prelude: prelude.to_string(),
+            items,
+        }
+    }
+
+    #[test]
+    fn parses_rules_and_strips_comments() {
+        let items = parse("/* reset */ .px-4{padding-inline:1rem} .a,.b{color:red !important}");
+
+        assert_eq!(
+            items,
+            vec![
+                rule(".px-4", vec![declaration("padding-inline", "1rem")]),
+                rule(
+                    ".a,.b",
+                    vec![Declaration {
+                        important: true,
+                        ..declaration("color", "red")
+                    }]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn flattens_nested_media_variants() {
+        let items = parse(r".sm\:p-8{@media (width >= 40rem){padding:2rem}}");
+
+        assert_eq!(
+            items,
+            vec![block(
+                "@media (width >= 40rem)",
+                vec![rule(r".sm\:p-8", vec![declaration("padding", "2rem")])]
+            )]
+        );
+    }
+
+    #[test]
+    fn flattens_nested_state_variants() {
+        let items = parse(
+            r".hover\:bg-red-500{&:hover{@media (hover: hover){background-color:var(--color-red-500)}}}",
+        );
+
+        assert_eq!(
+            items,
+            vec![block(
+                "@media (hover: hover)",
+                vec![rule(
+                    r".hover\:bg-red-500:hover",
+                    vec![declaration("background-color", "var(--color-red-500)")]
+                )]
+            )]
+        );
+    }
+
+    #[test]
+    fn keeps_declarations_next_to_nested_rules() {
+        let items = parse(".card{padding:1rem;& p{margin:0}.title{font-weight:700}}");
+
+        assert_eq!(
+            items,
+            vec![
+                rule(".card", vec![declaration("padding", "1rem")]),
+                rule(".card p", vec![declaration("margin", "0")]),
+                rule(".card .title", vec![declaration("font-weight", "700")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_layers_and_other_at_rules() {
+        let items = parse(
+            "@layer theme, base;@layer theme{:root{--spacing:0.25rem}}@property --tw-x{syntax:\"*\";inherits:false}",
+        );
+
+        assert_eq!(
+            items,
+            vec![
+                Item::AtRule {
+                    prelude: "@layer theme, base".to_string(),
+                    body: None,
+                },
+                block(
+                    "@layer theme",
+                    vec![rule(":root", vec![declaration("--spacing", "0.25rem")])]
+                ),
+                Item::AtRule {
+                    prelude: "@property --tw-x".to_string(),
+                    body: Some("syntax:\"*\";inherits:false".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_delimiters_in_strings_and_urls() {
+        let items = parse(r#".a{content:"{;}";background:url(data:image/png;base64,AA==)}"#);
+
+        assert_eq!(
+            items,
+            vec![rule(
+                ".a",
+                vec![
+                    declaration("content", "\"{;}\""),
+                    declaration("background", "url(data:image/png;base64,AA==)"),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn splits_selectors_outside_of_parentheses() {
+        assert_eq!(
+            split_selectors(".a, :is(.b, .c) > p,"),
+            vec![".a", ":is(.b, .c) > p"]
+        );
+    }
+
+    #[test]
+    fn writes_items_back_out() {
+        let css = r"@media (width >= 40rem){.sm\:p-8{padding:2rem}}.a{color:red !important}";
+        assert_eq!(to_css(&parse(css)), css);
+    }
+}
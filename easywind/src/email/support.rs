@@ -0,0 +1,229 @@
+use super::stylesheet::Declaration;
+
+// properties most email clients ignore, from caniemail.com. Gmail and Outlook on windows are
+// usually the reason
+static UNSUPPORTED_PROPERTIES: &[&str] = &[
+    "position",
+    "inset",
+    "top",
+    "right",
+    "bottom",
+    "left",
+    "z-index",
+    "transform",
+    "translate",
+    "rotate",
+    "scale",
+    "transition",
+    "animation",
+    "box-shadow",
+    "filter",
+    "backdrop-filter",
+    "clip-path",
+    "mask",
+    "gap",
+    "row-gap",
+    "column-gap",
+    "object-fit",
+    "object-position",
+    "aspect-ratio",
+    "flex",
+    "flex-direction",
+    "flex-wrap",
+    "flex-grow",
+    "flex-shrink",
+    "flex-basis",
+    "order",
+    "justify-content",
+    "justify-items",
+    "justify-self",
+    "align-content",
+    "align-items",
+    "align-self",
+    "place-content",
+    "place-items",
+    "place-self",
+];
+
+static UNSUPPORTED_PREFIXES: &[&str] = &["grid", "scroll-", "overscroll-", "container"];
+
+static UNSUPPORTED_DISPLAYS: &[&str] = &["flex", "inline-flex", "grid", "inline-grid", "contents"];
+
+// only functions that are left after values are resolved
+static UNSUPPORTED_FUNCTIONS: &[&str] = &[
+    "var(",
+    "calc(",
+    "min(",
+    "max(",
+    "clamp(",
+    "oklch(",
+    "oklab(",
+    "lab(",
+    "lch(",
+    "color-mix(",
+];
+
+// logical properties tailwind v4 uses for `px-*`, `my-*` or `border-x`, and the sides they
+// become in a left to right email
+static LOGICAL_SIDES: &[(&str, &[&str])] = &[
+    ("-inline-start", &["-left"]),
+    ("-inline-end", &["-right"]),
+    ("-block-start", &["-top"]),
+    ("-block-end", &["-bottom"]),
+    ("-inline", &["-left", "-right"]),
+    ("-block", &["-top", "-bottom"]),
+];
+
+static LOGICAL_PROPERTIES: &[&str] = &["padding-", "margin-", "border-"];
+
+/// `padding-inline: 1rem` -> `padding-left: 1rem; padding-right: 1rem`, most email clients
+/// don't support logical properties
+pub fn physical(declaration: Declaration) -> Vec<Declaration> {
+    let name = declaration.name.as_str();
+
+    if !LOGICAL_PROPERTIES
+        .iter()
+        .any(|property| name.starts_with(property))
+    {
+        return vec![declaration];
+    }
+
+    let Some((logical, sides)) = LOGICAL_SIDES
+        .iter()
+        .find(|(logical, _)| name.contains(logical))
+    else {
+        return vec![declaration];
+    };
+
+    // `padding-inline: 1rem 2rem` sets the start and end separately
+    let values = declaration.value.split_whitespace().collect::<Vec<_>>();
+    let split = sides.len() == 2 && values.len() == 2 && !name.starts_with("border-");
+
+    sides
+        .iter()
+        .enumerate()
+        .map(|(index, side)| Declaration {
+            name: name.replacen(logical, side, 1),
+            value: match split {
+                true => values[index].to_string(),
+                false => declaration.value.clone(),
+            },
+            important: declaration.important,
+        })
+        .collect()
+}
+
+/// Why the declaration won't work in most email clients, if it won't
+pub fn unsupported(declaration: &Declaration) -> Option<String> {
+    let name = declaration.name.as_str();
+    let value = declaration.value.as_str();
+
+    if UNSUPPORTED_PROPERTIES.contains(&name) {
+        return Some(format!("`{name}` is not supported by most email clients"));
+    }
+
+    if UNSUPPORTED_PREFIXES
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+    {
+        return Some(format!("`{name}` is not supported by most email clients"));
+    }
+
+    if name == "display" && UNSUPPORTED_DISPLAYS.contains(&value) {
+        return Some(format!(
+            "`display: {value}` is not supported by most email clients, use tables for layout"
+        ));
+    }
+
+    UNSUPPORTED_FUNCTIONS
+        .iter()
+        .find(|function| value.contains(*function))
+        .map(|function| {
+            format!(
+                "`{name}: {value}` uses `{})`, which is not supported by most email clients",
+                function
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn declaration(name: &str, value: &str) -> Declaration {
+        Declaration {
+            name: name.to_string(),
+            value: value.to_string(),
+            important: false,
+        }
+    }
+
+    fn physical_css(name: &str, value: &str) -> Vec<String> {
+        physical(declaration(name, value))
+            .into_iter()
+            .map(|declaration| format!("{}: {}", declaration.name, declaration.value))
+            .collect()
+    }
+
+    #[test]
+    fn splits_both_sides_of_an_axis() {
+        assert_eq!(
+            physical_css("padding-inline", "1rem"),
+            ["padding-left: 1rem", "padding-right: 1rem"]
+        );
+        assert_eq!(
+            physical_css("margin-block", "auto"),
+            ["margin-top: auto", "margin-bottom: auto"]
+        );
+    }
+
+    #[test]
+    fn splits_start_and_end_values() {
+        assert_eq!(
+            physical_css("padding-inline", "1rem 2rem"),
+            ["padding-left: 1rem", "padding-right: 2rem"]
+        );
+        // a border width, style and color go to both sides
+        assert_eq!(
+            physical_css("border-inline", "1px solid"),
+            ["border-left: 1px solid", "border-right: 1px solid"]
+        );
+    }
+
+    #[test]
+    fn maps_a_single_side() {
+        assert_eq!(
+            physical_css("margin-block-start", "1rem"),
+            ["margin-top: 1rem"]
+        );
+        assert_eq!(
+            physical_css("padding-inline-end", "2px"),
+            ["padding-right: 2px"]
+        );
+        assert_eq!(
+            physical_css("border-inline-start-width", "1px"),
+            ["border-left-width: 1px"]
+        );
+        assert_eq!(
+            physical_css("border-block-end-color", "#000"),
+            ["border-bottom-color: #000"]
+        );
+    }
+
+    #[test]
+    fn keeps_physical_properties() {
+        assert_eq!(physical_css("padding", "1rem 2rem"), ["padding: 1rem 2rem"]);
+        assert_eq!(physical_css("inline-size", "10px"), ["inline-size: 10px"]);
+    }
+
+    #[test]
+    fn keeps_important() {
+        let declarations = physical(Declaration {
+            important: true,
+            ..declaration("margin-inline", "auto")
+        });
+
+        assert_eq!(declarations.len(), 2);
+        assert!(declarations.iter().all(|declaration| declaration.important));
+    }
+}
@@ -0,0 +1,601 @@
+use std::collections::HashMap;
+
+// custom properties that reference each other deeper than this are treated as a cycle
+static MAX_VAR_DEPTH: usize = 16;
+
+/// Custom properties from `:root`, `@property` initial values and tailwind's `--tw-*`
+/// defaults, email clients don't support `var()` so it's replaced with these
+pub type Variables = HashMap<String, String>;
+
+/// Rewrites a value into something email clients understand: `var()` is replaced, simple
+/// `calc()`s are computed and colors are converted to hex, or `rgba()` when transparent.
+///
+/// Returns `None` when a variable has no value and no fallback
+pub fn resolve(value: &str, variables: &Variables) -> Option<String> {
+    let value = resolve_vars(value, variables, 0)?;
+    let value = replace_functions(&value, "calc(", evaluate_calc);
+    let value = replace_functions(&value, "oklch(", oklch_to_hex);
+    let value = replace_functions(&value, "oklab(", oklab_to_hex);
+    let value = replace_functions(&value, "rgb(", rgb_to_hex);
+    let value = replace_functions(&value, "rgba(", rgb_to_hex);
+    let value = replace_functions(&value, "color-mix(", mix_transparent);
+    let value = replace_hex_alpha(&value);
+
+    Some(value)
+}
+
+fn resolve_vars(value: &str, variables: &Variables, depth: usize) -> Option<String> {
+    if depth > MAX_VAR_DEPTH {
+        return None;
+    }
+
+    let mut resolved = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find("var(") {
+        resolved.push_str(&rest[..start]);
+
+        let arguments_start = start + "var(".len();
+        let end = closing_paren(rest, arguments_start)?;
+        let arguments = &rest[arguments_start..end];
+
+        let (name, fallback) = match split_top_level(arguments, ',') {
+            Some((name, fallback)) => (name.trim(), Some(fallback.trim())),
+            None => (arguments.trim(), None),
+        };
+
+        let replacement = match variables.get(name) {
+            Some(value) if value.trim() != "initial" => value.as_str(),
+            _ => fallback?,
+        };
+
+        resolved.push_str(&resolve_vars(replacement, variables, depth + 1)?);
+        rest = &rest[end + 1..];
+    }
+
+    resolved.push_str(rest);
+    Some(resolved)
+}
+
+// replaces every `name(...)` that `evaluate` can compute, innermost functions first
+fn replace_functions(value: &str, name: &str, evaluate: fn(&str) -> Option<String>) -> String {
+    let mut replaced = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = find_function(rest, name) {
+        let arguments_start = start + name.len();
+
+        let Some(end) = closing_paren(rest, arguments_start) else {
+            break;
+        };
+
+        let arguments = replace_functions(&rest[arguments_start..end], name, evaluate);
+
+        replaced.push_str(&rest[..start]);
+        match evaluate(&arguments) {
+            Some(evaluated) => replaced.push_str(&evaluated),
+            None => replaced.push_str(&format!("{name}{arguments})")),
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    replaced.push_str(rest);
+    replaced
+}
+
+// `calc(` but not `-webkit-calc(` or `xcalc(`
+fn find_function(value: &str, name: &str) -> Option<usize> {
+    value
+        .match_indices(name)
+        .map(|(index, _)| index)
+        .find(|index| {
+            !value[..*index]
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+/// `calc(0.25rem * 4)` -> `1rem`, only a single multiplication, division, addition or
+/// subtraction of plain numbers and lengths is computed
+fn evaluate_calc(expression: &str) -> Option<String> {
+    let expression = expression.trim();
+
+    let (left, operator, right) = ['*', '/', '+', '-'].iter().find_map(|operator| {
+        // `+` and `-` need spaces around them in calc
+        let separator = match operator {
+            '+' | '-' => format!(" {operator} "),
+            _ => operator.to_string(),
+        };
+
+        let (left, right) = expression.split_once(&separator)?;
+        Some((left.trim(), *operator, right.trim()))
+    })?;
+
+    let (left, left_unit) = parse_number(left)?;
+    let (right, right_unit) = parse_number(right)?;
+
+    let (number, unit) = match (operator, left_unit, right_unit) {
+        ('*', unit, "") | ('*', "", unit) => {
+            (left * right, if unit.is_empty() { left_unit } else { unit })
+        }
+        ('/', unit, "") if right != 0.0 => (left / right, unit),
+        ('+', unit, other) if unit == other => (left + right, unit),
+        ('-', unit, other) if unit == other => (left - right, unit),
+        _ => return None,
+    };
+
+    Some(format!("{}{unit}", format_number(number)))
+}
+
+// `0.25rem` -> (0.25, "rem")
+fn parse_number(value: &str) -> Option<(f64, &str)> {
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+' || c == 'e'))
+        .unwrap_or(value.len());
+
+    // `e` can also start a unit, ex: `1em`
+    let split = match value[..split].rfind('e') {
+        Some(index)
+            if !value[index + 1..].starts_with(|c: char| c.is_ascii_digit() || c == '-') =>
+        {
+            index
+        }
+        _ => split,
+    };
+
+    let (number, unit) = value.split_at(split);
+    let number = number.parse::<f64>().ok()?;
+
+    let is_unit = unit.chars().all(|c| c.is_ascii_alphabetic() || c == '%');
+    is_unit.then_some((number, unit))
+}
+
+fn format_number(number: f64) -> String {
+    let rounded = (number * 10_000.0).round() / 10_000.0;
+    let formatted = format!("{rounded}");
+
+    if formatted == "-0" {
+        "0".to_string()
+    } else {
+        formatted
+    }
+}
+
+/// `oklch(63.7% 0.237 25.331)` -> `#fb2c36`, with an alpha `rgba()` is used instead
+fn oklch_to_hex(arguments: &str) -> Option<String> {
+    let (channels, alpha) = color_channels(arguments)?;
+    let [lightness, chroma, hue] = channels;
+
+    let hue = hue.to_radians();
+    oklab_color(lightness, chroma * hue.cos(), chroma * hue.sin(), alpha)
+}
+
+fn oklab_to_hex(arguments: &str) -> Option<String> {
+    let (channels, alpha) = color_channels(arguments)?;
+    let [lightness, a, b] = channels;
+
+    oklab_color(lightness, a, b, alpha)
+}
+
+// `63.7% 0.237 25.331 / 50%` -> ([0.637, 0.237, 25.331], 0.5)
+fn color_channels(arguments: &str) -> Option<([f64; 3], f64)> {
+    let (channels, alpha) = match arguments.split_once('/') {
+        Some((channels, alpha)) => (channels, Some(alpha.trim())),
+        None => (arguments, None),
+    };
+
+    let parse = |value: &str| match value.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().ok().map(|percent| percent / 100.0),
+        None if value == "none" => Some(0.0),
+        None => value
+            .strip_suffix("deg")
+            .unwrap_or(value)
+            .parse::<f64>()
+            .ok(),
+    };
+
+    let channels = channels
+        .split_whitespace()
+        .map(parse)
+        .collect::<Option<Vec<_>>>()?;
+
+    let alpha = match alpha {
+        Some(alpha) => parse(alpha)?,
+        None => 1.0,
+    };
+
+    Some((channels.try_into().ok()?, alpha))
+}
+
+// https://bottosson.github.io/posts/oklab/
+fn oklab_color(lightness: f64, a: f64, b: f64, alpha: f64) -> Option<String> {
+    let l = (lightness + 0.396_337_777_4 * a + 0.215_803_757_3 * b).powi(3);
+    let m = (lightness - 0.105_561_345_8 * a - 0.063_854_172_8 * b).powi(3);
+    let s = (lightness - 0.089_484_177_5 * a - 1.291_485_548 * b).powi(3);
+
+    let linear = [
+        4.076_741_662_1 * l - 3.307_711_591_3 * m + 0.230_969_929_2 * s,
+        -1.268_438_004_6 * l + 2.609_757_401_1 * m - 0.341_319_396_5 * s,
+        -0.004_196_086_3 * l - 0.703_418_614_7 * m + 1.707_614_701 * s,
+    ];
+
+    // colors outside of srgb are clipped, close enough for email
+    let rgb = linear.map(|channel| {
+        let channel = channel.clamp(0.0, 1.0);
+
+        let gamma = if channel <= 0.003_130_8 {
+            12.92 * channel
+        } else {
+            1.055 * channel.powf(1.0 / 2.4) - 0.055
+        };
+
+        (gamma * 255.0).round() as u8
+    });
+
+    Some(format_color(rgb, alpha))
+}
+
+/// `rgb(0 0 0 / 0.1)` -> `rgba(0,0,0,0.1)`, older clients only know the comma syntax
+fn rgb_to_hex(arguments: &str) -> Option<String> {
+    let (rgb, alpha) = parse_rgb(arguments)?;
+    Some(format_color(rgb, alpha))
+}
+
+fn parse_rgb(arguments: &str) -> Option<([u8; 3], f64)> {
+    let arguments = arguments.replace(',', " ").replace('/', " / ");
+
+    let (channels, alpha) = match arguments.split_once('/') {
+        Some((channels, alpha)) => (channels, Some(alpha.trim())),
+        None => (arguments.as_str(), None),
+    };
+
+    let mut channels = channels.split_whitespace().collect::<Vec<_>>();
+
+    // the comma syntax puts the alpha last, `rgba(0, 0, 0, 0.1)`
+    let alpha = match (alpha, channels.len()) {
+        (Some(alpha), 3) => alpha,
+        (None, 4) => channels.pop()?,
+        (None, 3) => "1",
+        _ => return None,
+    };
+
+    let channel = |value: &str| match value.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().ok().map(|percent| percent * 2.55),
+        None => value.parse::<f64>().ok(),
+    };
+
+    let rgb = [
+        channel(channels[0])?,
+        channel(channels[1])?,
+        channel(channels[2])?,
+    ]
+    .map(|channel| channel.round().clamp(0.0, 255.0) as u8);
+
+    let alpha = match alpha.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().ok()? / 100.0,
+        None => alpha.parse::<f64>().ok()?,
+    };
+
+    Some((rgb, alpha))
+}
+
+/// `color-mix(in oklab, #fb2c36 50%, transparent)` -> `rgba(251,44,54,0.5)`, how tailwind
+/// applies opacity modifiers like `bg-red-500/50`
+fn mix_transparent(arguments: &str) -> Option<String> {
+    let mut parts = split_all(arguments, ',').into_iter().map(str::trim);
+
+    if !parts.next()?.starts_with("in ") {
+        return None;
+    }
+
+    let (color, other) = (parts.next()?, parts.next()?);
+
+    if parts.next().is_some() {
+        return None;
+    }
+
+    // the transparent side can come first too
+    let (color, other) = match color.starts_with("transparent") {
+        true => (other, color),
+        false => (color, other),
+    };
+
+    let other_percent = match other.strip_prefix("transparent")?.trim() {
+        "" => None,
+        percent => Some(parse_percent(percent)?),
+    };
+
+    let (color, percent) = match color.rsplit_once(' ') {
+        Some((color, percent)) if percent.ends_with('%') => {
+            (color.trim(), Some(parse_percent(percent)?))
+        }
+        _ => (color, None),
+    };
+
+    let weight = match (percent, other_percent) {
+        (Some(percent), _) => percent,
+        (None, Some(other)) => 1.0 - other,
+        (None, None) => 0.5,
+    };
+
+    let (rgb, alpha) = parse_color(color)?;
+    Some(format_color(rgb, alpha * weight.clamp(0.0, 1.0)))
+}
+
+fn parse_percent(percent: &str) -> Option<f64> {
+    Some(percent.strip_suffix('%')?.parse::<f64>().ok()? / 100.0)
+}
+
+// only the formats that are left once values are resolved
+fn parse_color(color: &str) -> Option<([u8; 3], f64)> {
+    if let Some(arguments) = color
+        .strip_prefix("rgba(")
+        .or_else(|| color.strip_prefix("rgb("))
+    {
+        return parse_rgb(arguments.strip_suffix(')')?);
+    }
+
+    parse_hex(color.strip_prefix('#')?)
+}
+
+// `fff`, `ffff`, `ffffff` or `ffffff80`
+fn parse_hex(hex: &str) -> Option<([u8; 3], f64)> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let digits = match hex.len() {
+        3 | 4 => hex.chars().flat_map(|c| [c, c]).collect::<String>(),
+        6 | 8 => hex.to_string(),
+        _ => return None,
+    };
+
+    let byte = |index: usize| u8::from_str_radix(&digits[index..index + 2], 16).ok();
+    let alpha = match digits.len() {
+        8 => byte(6)? as f64 / 255.0,
+        _ => 1.0,
+    };
+
+    Some(([byte(0)?, byte(2)?, byte(4)?], alpha))
+}
+
+/// `#0000` and `#fb2c3680` -> `rgba()`, most clients don't support hex with an alpha
+fn replace_hex_alpha(value: &str) -> String {
+    let mut replaced = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find('#') {
+        let digits = rest[start + 1..]
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(rest.len() - start - 1);
+
+        let hex = &rest[start + 1..start + 1 + digits];
+        replaced.push_str(&rest[..start]);
+
+        match parse_hex(hex) {
+            Some((rgb, alpha)) if hex.len() == 4 || hex.len() == 8 => {
+                replaced.push_str(&format_color(rgb, alpha))
+            }
+            _ => replaced.push_str(&rest[start..start + 1 + digits]),
+        }
+
+        rest = &rest[start + 1 + digits..];
+    }
+
+    replaced.push_str(rest);
+    replaced
+}
+
+fn format_color([red, green, blue]: [u8; 3], alpha: f64) -> String {
+    if alpha >= 1.0 {
+        format!("#{red:02x}{green:02x}{blue:02x}")
+    } else {
+        format!(
+            "rgba({red},{green},{blue},{})",
+            format_number(alpha.clamp(0.0, 1.0))
+        )
+    }
+}
+
+// the index of the `)` closing the function whose arguments start at `start`
+fn closing_paren(value: &str, start: usize) -> Option<usize> {
+    let mut depth = 1usize;
+
+    for (index, c) in value[start..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(start + index);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+// `--x, var(--y, 1px)` -> (`--x`, `var(--y, 1px)`)
+fn split_top_level(value: &str, separator: char) -> Option<(&str, &str)> {
+    let mut depth = 0usize;
+
+    for (index, c) in value.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            c if c == separator && depth == 0 => {
+                return Some((&value[..index], &value[index + 1..]))
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+// `in oklab, #fff 50%, transparent` -> [`in oklab`, ` #fff 50%`, ` transparent`]
+fn split_all(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = value;
+
+    while let Some((part, next)) = split_top_level(rest, separator) {
+        parts.push(part);
+        rest = next;
+    }
+
+    parts.push(rest);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables(pairs: &[(&str, &str)]) -> Variables {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn replaces_variables() {
+        let variables = variables(&[("--spacing", "0.25rem"), ("--color", "#fff")]);
+
+        assert_eq!(
+            resolve("var(--spacing) var(--color)", &variables).as_deref(),
+            Some("0.25rem #fff")
+        );
+    }
+
+    #[test]
+    fn uses_fallbacks_for_missing_and_initial_variables() {
+        let variables = variables(&[("--tw-ring", "initial"), ("--b", "2px")]);
+
+        assert_eq!(
+            resolve("var(--missing, 1px)", &variables).as_deref(),
+            Some("1px")
+        );
+        assert_eq!(
+            resolve("var(--tw-ring, 0 0 #0000)", &variables).as_deref(),
+            Some("0 0 rgba(0,0,0,0)")
+        );
+        assert_eq!(
+            resolve("var(--a, var(--b, 3px))", &variables).as_deref(),
+            Some("2px")
+        );
+        assert_eq!(
+            resolve("var(--a, var(--c, 3px))", &variables).as_deref(),
+            Some("3px")
+        );
+    }
+
+    #[test]
+    fn fails_without_a_value_or_fallback() {
+        assert_eq!(resolve("var(--missing)", &Variables::new()), None);
+    }
+
+    #[test]
+    fn fails_on_variable_cycles() {
+        let variables = variables(&[
+            ("--a", "var(--b)"),
+            ("--b", "var(--a)"),
+            ("--self", "var(--self, 1px)"),
+        ]);
+
+        assert_eq!(resolve("var(--a)", &variables), None);
+        assert_eq!(resolve("var(--self)", &variables), None);
+    }
+
+    #[test]
+    fn computes_simple_calcs() {
+        let variables = variables(&[("--spacing", "0.25rem")]);
+
+        assert_eq!(
+            resolve("calc(var(--spacing) * 4)", &variables).as_deref(),
+            Some("1rem")
+        );
+        assert_eq!(
+            resolve("calc(var(--spacing) * -2)", &variables).as_deref(),
+            Some("-0.5rem")
+        );
+        assert_eq!(
+            resolve("calc(1em / 3)", &variables).as_deref(),
+            Some("0.3333em")
+        );
+        assert_eq!(
+            resolve("calc(1rem + 2rem)", &variables).as_deref(),
+            Some("3rem")
+        );
+        assert_eq!(
+            resolve("calc(100% - 2rem)", &variables).as_deref(),
+            Some("calc(100% - 2rem)")
+        );
+    }
+
+    #[test]
+    fn converts_tailwind_colors_to_hex() {
+        let colors = [
+            ("oklch(63.7% 0.237 25.331)", "#fb2c36"),
+            ("oklch(62.3% 0.214 259.815)", "#2b7fff"),
+            ("oklch(72.3% 0.219 149.579)", "#00c950"),
+            ("oklch(21% 0.034 264.665)", "#101828"),
+            ("oklch(100% 0 0)", "#ffffff"),
+            ("oklch(0% 0 0)", "#000000"),
+            ("oklab(62.8% 0.225 0.126)", "#ff0000"),
+        ];
+
+        for (color, hex) in colors {
+            assert_eq!(
+                resolve(color, &Variables::new()).as_deref(),
+                Some(hex),
+                "{color}"
+            );
+        }
+    }
+
+    #[test]
+    fn converts_transparent_colors_to_rgba() {
+        let variables = Variables::new();
+
+        assert_eq!(
+            resolve("oklch(63.7% 0.237 25.331 / 50%)", &variables).as_deref(),
+            Some("rgba(251,44,54,0.5)")
+        );
+        assert_eq!(
+            resolve("rgb(0 0 0 / 0.1)", &variables).as_deref(),
+            Some("rgba(0,0,0,0.1)")
+        );
+        assert_eq!(
+            resolve("rgba(255, 255, 255, 1)", &variables).as_deref(),
+            Some("#ffffff")
+        );
+        assert_eq!(
+            resolve("#fb2c3680", &variables).as_deref(),
+            Some("rgba(251,44,54,0.502)")
+        );
+    }
+
+    #[test]
+    fn resolves_opacity_modifiers() {
+        let variables = variables(&[("--color-red-500", "oklch(63.7% 0.237 25.331)")]);
+
+        assert_eq!(
+            resolve(
+                "color-mix(in oklab, var(--color-red-500) 50%, transparent)",
+                &variables
+            )
+            .as_deref(),
+            Some("rgba(251,44,54,0.5)")
+        );
+        assert_eq!(
+            resolve("color-mix(in srgb, transparent 75%, #000)", &variables).as_deref(),
+            Some("rgba(0,0,0,0.25)")
+        );
+    }
+}
@@ -1,14 +1,25 @@
 use eyre::{eyre, Result};
 use log::info;
 
-use crate::template::{TemplateName, TEMPLATE};
+use crate::{
+    tailwind::{
+        installer::DownloadOptions,
+        version::{TailwindMajor, TailwindVersion},
+    },
+    template::{TemplateName, TEMPLATE},
+};
 
 pub struct InitArgs {
     pub project_name: String,
+    pub tailwind: TailwindMajor,
+    pub download: DownloadOptions,
 }
 
 pub fn run(args: InitArgs) -> Result<()> {
-    crate::tailwind::installer::check_or_install()?;
+    crate::tailwind::installer::check_or_install(
+        &TailwindVersion::latest(args.tailwind),
+        &args.download,
+    )?;
 
     // create dirs
     create_project_dir(&args.project_name)?;
@@ -16,11 +27,13 @@ pub fn run(args: InitArgs) -> Result<()> {
     std::fs::create_dir_all(format!("{}/dist", args.project_name))?;
 
     // create files
-    // tailwind.config.js
-    let ctx: minijinja::Value = minijinja::context! {};
-    let template = TEMPLATE.render(TemplateName::TailwindConfig, &ctx);
-    let file_path = format!("{}/tailwind.config.js", args.project_name);
-    std::fs::write(file_path, template)?;
+    // tailwind.config.js, v4 is configured in the css file instead
+    if args.tailwind == TailwindMajor::V3 {
+        let ctx: minijinja::Value = minijinja::context! {};
+        let template = TEMPLATE.render(TemplateName::TailwindConfig, &ctx);
+        let file_path = format!("{}/tailwind.config.js", args.project_name);
+        std::fs::write(file_path, template)?;
+    }
 
     // index.html
     let ctx: minijinja::Value = minijinja::context! { project_name => args.project_name.clone() };
@@ -30,7 +43,11 @@ pub fn run(args: InitArgs) -> Result<()> {
 
     // src/app.css
     let ctx: minijinja::Value = minijinja::context! { project_name => args.project_name.clone() };
-    let template = TEMPLATE.render(TemplateName::ProjectCss, &ctx);
+    let css_template = match args.tailwind {
+        TailwindMajor::V3 => TemplateName::ProjectCssV3,
+        TailwindMajor::V4 => TemplateName::ProjectCss,
+    };
+    let template = TEMPLATE.render(css_template, &ctx);
     let file_path = format!("{}/src/app.css", args.project_name);
     std::fs::write(file_path, template)?;
 
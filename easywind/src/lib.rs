@@ -1,7 +1,12 @@
+pub mod build;
+pub mod config;
 pub mod consts;
+pub mod email;
 pub mod init;
+pub mod render;
 pub mod server;
 pub mod start;
 pub mod tailwind;
 pub mod template;
 pub mod validate;
+pub mod watch;
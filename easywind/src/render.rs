@@ -0,0 +1,236 @@
+mod markdown;
+
+use std::path::{Component, Path, PathBuf};
+
+use minijinja::{Environment, Value};
+use serde::Serialize;
+use thiserror::Error;
+
+/// Added to a page to have it rendered, ex: `about.html.j2` is served as `/about.html`
+pub static TEMPLATE_SUFFIX: &str = ".j2";
+
+/// Directories searched for templates that aren't found relative to the root dir, so pages can
+/// use `{% extends "base.html" %}` instead of `{% extends "layouts/base.html" %}`
+pub static TEMPLATE_DIRS: &[&str] = &["layouts", "partials"];
+
+/// Layout for markdown pages without a `layout` in their front matter, falls back to the
+/// built in one when the project doesn't have it
+pub static MARKDOWN_LAYOUT: &str = "layouts/markdown.html";
+
+static BUILT_IN_MARKDOWN_LAYOUT: &str = "__easywind_markdown.html";
+
+/// A page that failed to render, with the part of the template that caused it
+#[derive(Error, Debug, Clone)]
+#[error("{message}")]
+pub struct RenderError {
+    pub message: String,
+    /// The template source around the line that failed
+    pub detail: Option<String>,
+}
+
+impl From<minijinja::Error> for RenderError {
+    fn from(error: minijinja::Error) -> Self {
+        let detail = error.display_debug_info().to_string();
+
+        Self {
+            message: error.to_string(),
+            detail: (!detail.trim().is_empty()).then_some(detail),
+        }
+    }
+}
+
+/// Available in every page as `page`, ex: `{{ page.url }}`
+#[derive(Debug, Clone, Serialize)]
+pub struct PageContext {
+    /// The url the page is served at, ex: `/blog/`
+    pub url: String,
+    /// The file that was rendered, relative to the root dir
+    pub file: String,
+    /// From the front matter of markdown pages, or the file name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+/// Renders user pages with minijinja, `*.html.j2` files, `.html` files that extend a layout
+/// and markdown files
+#[derive(Debug, Clone)]
+pub struct Renderer {
+    root_dir: PathBuf,
+    /// Url of the project's compiled css, linked from the built in markdown layout
+    stylesheet: Option<String>,
+}
+
+impl Renderer {
+    /// `root_dir` should be canonical, templates outside of it can't be loaded
+    pub fn new(root_dir: &Path) -> Self {
+        Self {
+            root_dir: root_dir.to_path_buf(),
+            stylesheet: None,
+        }
+    }
+
+    pub fn stylesheet(mut self, url: Option<String>) -> Self {
+        self.stylesheet = url;
+        self
+    }
+
+    pub fn is_markdown(path: &Path) -> bool {
+        path.extension()
+            .is_some_and(|extension| extension == "md" || extension == "markdown")
+    }
+
+    /// Whether the file is a page that needs to be rendered
+    pub fn is_page(path: &Path) -> bool {
+        let name = path.to_string_lossy();
+
+        if name.ends_with(".html.j2") {
+            return true;
+        }
+
+        name.ends_with(".html")
+            && std::fs::read_to_string(path).is_ok_and(|contents| extends_layout(&contents))
+    }
+
+    /// Renders the page at `path`, templates are read from disk on every render so changes
+    /// show up on the next reload
+    pub fn render(&self, path: &Path, url: &str) -> Result<String, RenderError> {
+        let name = self.template_name(path);
+
+        let env = self.environment();
+        let template = env.get_template(&name)?;
+
+        let page = PageContext {
+            url: url.to_string(),
+            file: name.clone(),
+            title: None,
+        };
+
+        Ok(template.render(minijinja::context! { page => page })?)
+    }
+
+    /// Renders a markdown file into its layout, the front matter is available as `meta` and
+    /// the html as `content`
+    pub fn render_markdown(&self, path: &Path, url: &str) -> Result<String, RenderError> {
+        let file = self.template_name(path);
+
+        let source = std::fs::read_to_string(path).map_err(|error| RenderError {
+            message: format!("unable to read {file}: {error}"),
+            detail: None,
+        })?;
+
+        let document = markdown::Document::parse(&source);
+
+        let meta: Value = match document.front_matter {
+            Some(front_matter) => {
+                serde_norway::from_str(front_matter).map_err(|error| RenderError {
+                    message: format!("invalid front matter in {file}: {error}"),
+                    detail: Some(front_matter.to_string()),
+                })?
+            }
+            None => Value::from(()),
+        };
+
+        let title = meta
+            .get_attr("title")
+            .ok()
+            .filter(|title| !title.is_undefined() && !title.is_none())
+            .map(|title| title.to_string())
+            .or_else(|| {
+                path.file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+            });
+
+        let layout = meta
+            .get_attr("layout")
+            .ok()
+            .and_then(|layout| layout.as_str().map(ToString::to_string));
+
+        let env = self.environment();
+
+        let template = match layout {
+            Some(layout) => env.get_template(&layout)?,
+            None if self.root_dir.join(MARKDOWN_LAYOUT).is_file() => {
+                env.get_template(MARKDOWN_LAYOUT)?
+            }
+            None => env.get_template(BUILT_IN_MARKDOWN_LAYOUT)?,
+        };
+
+        let page = PageContext {
+            url: url.to_string(),
+            file,
+            title,
+        };
+
+        Ok(template.render(minijinja::context! {
+            page => page,
+            meta => meta,
+            content => Value::from_safe_string(document.to_html()),
+            stylesheet => self.stylesheet,
+        })?)
+    }
+
+    // the path relative to the root dir with `/` separators, which is how templates are named
+    fn template_name(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root_dir)
+            .unwrap_or(path)
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn environment(&self) -> Environment<'static> {
+        let root_dir = self.root_dir.clone();
+
+        let mut env = Environment::new();
+        // only on by default in debug builds, errors show the failing line from the source
+        env.set_debug(true);
+        env.set_loader(move |name| load_template(&root_dir, name));
+
+        env.add_template(
+            BUILT_IN_MARKDOWN_LAYOUT,
+            include_str!("../templates/markdown.html.j2"),
+        )
+        .expect("built in markdown layout is valid");
+
+        env
+    }
+}
+
+fn load_template(root_dir: &Path, name: &str) -> Result<Option<String>, minijinja::Error> {
+    let relative = Path::new(name);
+
+    // `..` or absolute names would let a template read files outside the root dir
+    let is_relative = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+
+    if !is_relative {
+        return Ok(None);
+    }
+
+    let candidates = std::iter::once(root_dir.to_path_buf())
+        .chain(TEMPLATE_DIRS.iter().map(|dir| root_dir.join(dir)))
+        .map(|dir| dir.join(relative));
+
+    for candidate in candidates {
+        match std::fs::read_to_string(&candidate) {
+            Ok(contents) => return Ok(Some(contents)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(error) => {
+                return Err(minijinja::Error::new(
+                    minijinja::ErrorKind::InvalidOperation,
+                    format!("unable to read template {}", candidate.display()),
+                )
+                .with_source(error))
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+// plain html files opt in to rendering by extending a layout
+fn extends_layout(contents: &str) -> bool {
+    contents.contains("{% extends") || contents.contains("{%- extends")
+}
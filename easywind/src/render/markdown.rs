@@ -0,0 +1,67 @@
+use pulldown_cmark::{html, Options, Parser};
+
+/// A markdown file split into its front matter and body
+pub struct Document<'a> {
+    /// The yaml between the `---` lines at the top of the file, if there is any
+    pub front_matter: Option<&'a str>,
+    pub body: &'a str,
+}
+
+impl<'a> Document<'a> {
+    pub fn parse(source: &'a str) -> Self {
+        let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+
+        let front_matter = source
+            .strip_prefix("---")
+            .and_then(|rest| {
+                rest.strip_prefix("\r\n")
+                    .or_else(|| rest.strip_prefix('\n'))
+            })
+            .and_then(|rest| {
+                let end = closing_delimiter(rest)?;
+                let body = rest[end..].split_once('\n').map_or("", |(_, body)| body);
+
+                Some((&rest[..end], body))
+            });
+
+        match front_matter {
+            Some((front_matter, body)) => Self {
+                front_matter: Some(front_matter),
+                body,
+            },
+            None => Self {
+                front_matter: None,
+                body: source,
+            },
+        }
+    }
+
+    /// The body as html, with github flavored tables, task lists, strikethrough and footnotes
+    pub fn to_html(&self) -> String {
+        let options = Options::ENABLE_TABLES
+            | Options::ENABLE_FOOTNOTES
+            | Options::ENABLE_STRIKETHROUGH
+            | Options::ENABLE_TASKLISTS
+            | Options::ENABLE_HEADING_ATTRIBUTES;
+
+        let mut html = String::new();
+        html::push_html(&mut html, Parser::new_ext(self.body, options));
+
+        html
+    }
+}
+
+// start of the `---` line that closes the front matter
+fn closing_delimiter(rest: &str) -> Option<usize> {
+    let mut offset = 0;
+
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return Some(offset);
+        }
+
+        offset += line.len();
+    }
+
+    None
+}
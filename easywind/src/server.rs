@@ -1,32 +1,54 @@
+pub mod access;
+pub mod address;
+pub mod cache;
 pub mod error;
+pub mod error_page;
+pub mod file;
+pub mod livereload;
 pub mod no_cache;
 pub mod port;
+pub mod proxy;
 pub mod reload;
+pub mod resolve;
+pub mod rules;
 
 use axum::{
-    body::Bytes,
-    extract::{Path, State},
-    http::{self, header, HeaderMap, HeaderValue, StatusCode},
-    response::{Html, IntoResponse},
+    extract::State,
+    http::{self, header, HeaderMap, HeaderValue, Method, Uri},
+    response::{Html, IntoResponse, Redirect, Response},
     routing::get,
     Router,
 };
-use eyre::Result;
-use log::{error, info};
-use notify_debouncer_mini::{notify::RecursiveMode, DebounceEventResult};
-use tower_livereload::LiveReloadLayer;
+use color_eyre::Help;
+use eyre::{eyre, Result};
+use log::{error, info, warn};
+use notify::RecursiveMode;
+use tower_http::compression::CompressionLayer;
 
 use std::{
-    fmt::Display,
-    fs::{DirEntry, File as StdFile},
-    io::Read,
     net::SocketAddr,
-    path::PathBuf,
+    path::{Path, PathBuf},
+    sync::Arc,
     time::Duration,
 };
 
-use self::error::Error;
-use crate::template::{TemplateName, TEMPLATE};
+use self::{
+    access::{AccessPolicy, Denied, SymlinkPolicy},
+    address::Urls,
+    cache::CacheMode,
+    error::Error,
+    error_page::ErrorPages,
+    livereload::Reloader,
+    proxy::Proxy,
+    resolve::Resolved,
+    rules::SharedRules,
+};
+use crate::{
+    email::EmailCss,
+    render::Renderer,
+    template::{TemplateName, TEMPLATE},
+    watch::Watcher,
+};
 
 #[cfg(not(feature = "dev"))]
 static APP_CSS: &str = include_str!("../static/app.css");
@@ -34,84 +56,204 @@ static APP_CSS: &str = include_str!("../static/app.css");
 #[derive(Clone)]
 struct AppState {
     root_dir: PathBuf,
+    access: Arc<AccessPolicy>,
+    compress: bool,
+    spa: Option<PathBuf>,
+    stylesheet: Option<String>,
+    email: bool,
 }
 
 pub struct ServerArgs {
     pub root_dir: PathBuf,
+    /// Address to listen on, localhost only unless set to a LAN address or `0.0.0.0`
+    pub host: String,
     pub port: u16,
+    /// Fail if the port is taken instead of using another one
+    pub strict_port: bool,
     pub open: bool,
+    /// Backend to forward all unmatched requests to, ex: http://localhost:4000
+    pub proxy: Option<String>,
+    /// Shared with the tailwind watcher so build errors show up in the browser
+    pub reloader: Reloader,
+    /// Patterns of paths that are never served or listed, ex: `.*` for dotfiles
+    pub hidden: Vec<String>,
+    pub symlinks: SymlinkPolicy,
+    /// Caching headers sent to the browser, no caching at all by default
+    pub cache: CacheMode,
+    /// Compress responses with gzip or brotli and serve `.br` and `.gz` siblings of files
+    pub compress: bool,
+    /// Document served for html requests that match no file, for client side routing
+    pub spa: Option<PathBuf>,
+    /// Url of the compiled tailwind css, linked from the markdown layout, ex: `/dist/app.css`
+    pub stylesheet: Option<String>,
+    /// Css tailwind compiles, changing it or its imports waits for the compiled css to swap
+    pub tailwind_input: Option<PathBuf>,
+    /// Watch the root dir and add the livereload script to html, off when previewing a build
+    pub livereload: bool,
+    /// Inline the css into html pages the way `easywind build --email` does
+    pub email: bool,
 }
 
-#[derive(Debug)]
-enum File {
-    Dir(PathBuf),
-    File(PathBuf),
+fn canonicalize(path: &PathBuf) -> Result<PathBuf, Error> {
+    std::fs::canonicalize(path).map_err(|_| Error::InvalidRootDir(path.clone()))
 }
 
-impl Display for File {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(
-            match self {
-                Self::Dir(path) => path.to_string_lossy(),
-                Self::File(path) => path.to_string_lossy(),
-            }
-            .as_ref(),
-        )
+async fn serve(
+    State(state): State<AppState>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    info!("{method} {}", uri.path());
+
+    let root = canonicalize(&state.root_dir)?;
+
+    if let Err(denied) = state
+        .access
+        .check_relative(&resolve::relative_path(uri.path()))
+    {
+        return Err(denied_error(uri.path(), denied));
     }
-}
 
-impl From<DirEntry> for File {
-    fn from(dir_entry: DirEntry) -> Self {
-        let path = dir_entry.path();
-        if path.is_dir() {
-            Self::Dir(path)
-        } else {
-            Self::File(path)
+    let resolved = resolve::resolve(&root, uri.path());
+
+    if let Resolved::File(path) | Resolved::Directory(path) = &resolved {
+        if let Err(denied) = state.access.check(&root, path) {
+            return Err(denied_error(uri.path(), denied));
         }
     }
-}
 
-fn canonicalize(path: &PathBuf) -> Result<PathBuf, Error> {
-    std::fs::canonicalize(path).map_err(|_| Error::InvalidRootDir(path.clone()))
-}
+    match resolved {
+        // `?raw` shows the source of a page instead of rendering it
+        Resolved::File(path)
+            if is_raw(&uri) && (Renderer::is_markdown(&path) || Renderer::is_page(&path)) =>
+        {
+            let mut response = file::serve_file(&path, &method, &headers, false)
+                .await
+                .map_err(at_url(&uri))?;
 
-async fn root(State(state): State<AppState>) -> Result<Html<String>, Error> {
-    info!("GET /");
-    let root = canonicalize(&state.root_dir)?;
+            response.headers_mut().insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("text/plain; charset=utf-8"),
+            );
 
-    // if is a directory with an "index.html" file, serve that
-    if root.is_dir() {
-        if let Ok(index) = std::fs::read_to_string(root.join("index.html")) {
-            return Ok(Html(index));
+            Ok(response)
         }
+        Resolved::File(path) if Renderer::is_markdown(&path) => {
+            let html = Renderer::new(&root)
+                .stylesheet(state.stylesheet.clone())
+                .render_markdown(&path, uri.path())?;
+
+            Ok(Html(state.email_html(&root, uri.path(), html).await?).into_response())
+        }
+        Resolved::File(path) if Renderer::is_page(&path) => {
+            let html = Renderer::new(&root).render(&path, uri.path())?;
+            Ok(Html(state.email_html(&root, uri.path(), html).await?).into_response())
+        }
+        Resolved::File(path) if state.email && is_html(&path) => {
+            let html = tokio::fs::read_to_string(&path).await?;
+            Ok(Html(state.email_html(&root, uri.path(), html).await?).into_response())
+        }
+        Resolved::File(path) => file::serve_file(&path, &method, &headers, state.compress)
+            .await
+            .map_err(at_url(&uri)),
+        Resolved::Directory(path) => {
+            Ok(index_template(&root, path, &state.access)?.into_response())
+        }
+
+        // static hosts use permanent redirects, but the browser would cache those while
+        // the files are still changing
+        Resolved::Redirect(location) => {
+            let location = match uri.query() {
+                Some(query) => format!("{location}?{query}"),
+                None => location,
+            };
+
+            Ok(Redirect::temporary(&location).into_response())
+        }
+
+        // assets that don't exist are still a 404, only page loads get the fallback
+        Resolved::NotFound => match &state.spa {
+            Some(fallback) if accepts_html(&headers) => {
+                file::serve_file(&root.join(fallback), &method, &headers, state.compress)
+                    .await
+                    .map_err(at_url(&uri))
+            }
+            _ => Err(Error::NotFound(uri.path().to_string())),
+        },
     }
+}
 
-    index_template(&root, root.clone())
+// error pages name the url instead of the path on disk, they can be seen by anyone on the network
+fn at_url(uri: &Uri) -> impl FnOnce(Error) -> Error + '_ {
+    |error| match error {
+        Error::NotFound(_) => Error::NotFound(uri.path().to_string()),
+        Error::FileMetadataError(_) => Error::FileMetadataError(PathBuf::from(uri.path())),
+        error => error,
+    }
 }
 
-async fn path(
-    State(state): State<AppState>,
-    Path(path): Path<PathBuf>,
-) -> Result<impl IntoResponse, Error> {
-    info!("GET {}", path.to_string_lossy());
+impl AppState {
+    // the css is read on every request, so changes show up on the next reload
+    async fn email_html(&self, root: &Path, page_url: &str, html: String) -> Result<String, Error> {
+        if !self.email {
+            return Ok(html);
+        }
 
-    let root = canonicalize(&state.root_dir)?;
+        let stylesheet = self.stylesheet.as_deref();
+        let css = match stylesheet {
+            Some(stylesheet) => {
+                let path = root.join(stylesheet.trim_start_matches('/'));
+
+                // tailwind may not have built it yet, the page is still shown without styles
+                tokio::fs::read_to_string(&path)
+                    .await
+                    .unwrap_or_else(|error| {
+                        warn!("Unable to read {} to inline: {error}", path.display());
+                        String::new()
+                    })
+            }
+            None => String::new(),
+        };
 
-    let mut path_to_serve = root.clone();
-    path_to_serve.push(path);
+        let inlined = crate::email::inline(&html, &EmailCss::parse(&css), stylesheet, page_url)?;
 
-    // directory list all files
-    if path_to_serve.is_dir() {
-        return Ok(index_template(&root, path_to_serve).into_response());
-    };
+        for warning in &inlined.warnings {
+            warn!("{warning}");
+        }
 
-    // serve html files
-    if path_to_serve.ends_with(".html") {
-        return Ok(std::fs::read_to_string(path_to_serve)?.into_response());
+        Ok(inlined.html)
     }
+}
+
+pub(crate) fn is_html(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "html" || extension == "htm")
+}
+
+fn is_raw(uri: &Uri) -> bool {
+    uri.query().is_some_and(|query| {
+        query
+            .split('&')
+            .any(|param| param == "raw" || param.starts_with("raw="))
+    })
+}
 
-    // any other file, create response depending on mime type
-    Ok(static_path(path_to_serve).into_response())
+fn accepts_html(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/html"))
+}
+
+// hidden files respond as if they don't exist, so their names aren't revealed
+fn denied_error(path: &str, denied: Denied) -> Error {
+    warn!("Denied {path}: {denied}");
+
+    match denied {
+        Denied::Hidden => Error::NotFound(path.to_string()),
+        Denied::OutsideRoot | Denied::Symlink => Error::Forbidden(path.to_string()),
+    }
 }
 
 async fn serve_internal_css() -> impl IntoResponse {
@@ -125,7 +267,11 @@ async fn serve_internal_css() -> impl IntoResponse {
     (headers, APP_CSS)
 }
 
-fn index_template(root_dir: &PathBuf, path: PathBuf) -> Result<Html<String>, Error> {
+fn index_template(
+    root_dir: &PathBuf,
+    path: PathBuf,
+    access: &AccessPolicy,
+) -> Result<Html<String>, Error> {
     let root = canonicalize(root_dir)?;
 
     let mut current_dir = path
@@ -137,6 +283,7 @@ fn index_template(root_dir: &PathBuf, path: PathBuf) -> Result<Html<String>, Err
     let paths: Vec<PathBuf> = std::fs::read_dir(&path)?
         .filter_map(Result::ok)
         .map(|dir_entry| dir_entry.path())
+        .filter(|path| access.check(&root, path).is_ok())
         .collect::<Vec<_>>();
 
     let mut links = paths
@@ -165,39 +312,62 @@ fn index_template(root_dir: &PathBuf, path: PathBuf) -> Result<Html<String>, Err
     Ok(template.into())
 }
 
-fn static_path(path: PathBuf) -> Result<impl IntoResponse, Error> {
-    let mime_type = mime_guess::from_path(&path).first_or_text_plain();
+/// Content type from the file extension, text files are served as utf-8 like static hosts do
+pub(crate) fn content_type(path: &std::path::Path) -> HeaderValue {
+    let mime_type = mime_guess::from_path(path).first_or_octet_stream();
 
-    match StdFile::open(&path) {
-        Ok(mut file) => {
-            let mut buffer = Vec::new();
-            file.read_to_end(&mut buffer)
-                .map_err(|_| Error::FileReadBufferOverflow(path.clone()))?;
+    let is_text = mime_type.type_() == mime_guess::mime::TEXT
+        || matches!(
+            mime_type.subtype().as_str(),
+            "javascript" | "json" | "xml" | "svg"
+        );
 
-            let mut headers = HeaderMap::new();
-            headers.insert(
-                header::CONTENT_TYPE,
-                HeaderValue::from_str(mime_type.as_ref()).expect("Invalid mime type"),
-            );
+    let content_type = if is_text {
+        format!("{mime_type}; charset=utf-8")
+    } else {
+        mime_type.to_string()
+    };
 
-            Ok((StatusCode::OK, headers, Bytes::from(buffer)))
-        }
+    HeaderValue::from_str(&content_type).expect("Invalid mime type")
+}
 
-        Err(_) => Ok((
-            StatusCode::NOT_FOUND,
-            HeaderMap::new(),
-            Bytes::from("File not found"),
-        )),
+fn spa_fallback(root_dir: &Path, fallback: PathBuf) -> Result<PathBuf> {
+    if root_dir.join(&fallback).is_file() {
+        return Ok(fallback);
     }
+
+    Err(eyre!(
+        "SPA fallback not found: {}",
+        root_dir.join(&fallback).display()
+    ))
+    .suggestion("The fallback is relative to the root dir, ex: `--spa index.html`")
+}
+
+fn internal_routes<S>(reloader: Reloader) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route(
+            "/__internal_only_easywind_css_file__.css",
+            get(serve_internal_css),
+        )
+        .merge(livereload::routes(reloader))
 }
 
 pub async fn start(args: ServerArgs) -> Result<()> {
-    let port = port::default_or_available(args.port).expect("Unable to find available port");
+    let ip = address::resolve_host(&args.host)?;
+    let port = port::default_or_available(ip, args.port, args.strict_port)?;
+
+    let addr = SocketAddr::new(ip, port);
+    let urls = Urls::new(addr);
 
     if args.open {
+        let url = urls.local.clone();
+
         tokio::task::spawn(async move {
             tokio::time::sleep(Duration::from_millis(200)).await;
-            if let Err(error) = open::that(format!("http://localhost:{port}")) {
+            if let Err(error) = open::that(url) {
                 error!("Unable to open browser: {error:?}");
             }
         });
@@ -205,40 +375,99 @@ pub async fn start(args: ServerArgs) -> Result<()> {
 
     let state = AppState {
         root_dir: args.root_dir.clone(),
+        access: Arc::new(AccessPolicy::new(&args.hidden, args.symlinks)?),
+        compress: args.compress,
+        stylesheet: args.stylesheet.clone(),
+        email: args.email,
+        spa: args
+            .spa
+            .map(|fallback| spa_fallback(&args.root_dir, fallback))
+            .transpose()?,
     };
 
-    let livereload = LiveReloadLayer::new();
-    let reloader = livereload.reloader();
+    let rules = SharedRules::load(&args.root_dir);
 
-    let mut debouncer = notify_debouncer_mini::new_debouncer(
-        Duration::from_millis(80),
-        move |event: DebounceEventResult| {
-            let _ = reload::handle_reload(event, &reloader);
-        },
-    )
-    .unwrap();
+    // kept alive until the server stops
+    let _watcher = if args.livereload {
+        let reloader = args.reloader.clone();
+        let root_dir = args.root_dir.clone();
+        let tailwind_input = args.tailwind_input.clone();
+        let watched_rules = rules.clone();
 
-    debouncer
-        .watcher()
-        .watch(&args.root_dir, RecursiveMode::Recursive)?;
+        let mut watcher = Watcher::new(Duration::from_millis(80), move |changed| {
+            if watched_rules.reload_if_changed(&changed) {
+                reloader.reload();
+            }
 
-    let app = Router::new()
-        .route("/", get(root))
-        .route(
-            "/__internal_only_easywind_css_file__.css",
-            get(serve_internal_css),
-        )
-        .route("/*path", get(path))
-        .with_state(state.clone())
-        .layer(livereload)
-        .layer(no_cache::layer());
+            reload::handle_reload(changed, &reloader, &root_dir, tailwind_input.as_deref());
+        })?;
+
+        watcher.watch(&args.root_dir, RecursiveMode::Recursive)?;
+
+        Some(watcher)
+    } else {
+        None
+    };
+
+    let app = match args.proxy {
+        Some(target) => {
+            let proxy = Proxy::new(&target)?;
+            info!("Proxying requests to {}", proxy.target());
+
+            internal_routes(args.reloader)
+                .fallback(proxy::forward)
+                .with_state(proxy)
+                .layer(axum::middleware::from_fn_with_state(
+                    ErrorPages::default(),
+                    error_page::render,
+                ))
+        }
+
+        None => {
+            info!("Serving html from {}", state.root_dir.to_string_lossy());
+
+            let pages = ErrorPages {
+                root_dir: Some(state.root_dir.clone()),
+            };
+
+            // internal routes are merged after the rules, so a `/*` rewrite can't shadow them
+            Router::new()
+                .route("/", get(serve))
+                .route("/{*path}", get(serve))
+                .layer(axum::middleware::from_fn_with_state(rules, rules::apply))
+                .merge(internal_routes(args.reloader))
+                .with_state(state)
+                .layer(axum::middleware::from_fn_with_state(
+                    pages,
+                    error_page::render,
+                ))
+        }
+    };
+
+    // compression runs after the livereload script is added to the html
+    let app = if args.livereload {
+        app.layer(axum::middleware::from_fn(livereload::inject))
+    } else {
+        app
+    };
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let app = if args.compress {
+        app.layer(CompressionLayer::new())
+    } else {
+        app
+    };
+
+    let app = match args.cache {
+        CacheMode::None => app.layer(no_cache::layer()),
+        CacheMode::Revalidate => app.layer(cache::revalidate_layer()),
+        CacheMode::Immutable => app.layer(axum::middleware::from_fn(cache::immutable)),
+    };
 
-    info!("Serving html from {}", state.root_dir.to_string_lossy());
     info!("Starting server at {}", addr);
 
     let listener = tokio::net::TcpListener::bind(&addr).await?;
+    urls.print();
+
     axum::serve(listener, app.into_make_service()).await?;
 
     Ok(())
@@ -0,0 +1,160 @@
+use std::{
+    fmt::Display,
+    path::{Component, Path},
+    str::FromStr,
+};
+
+use eyre::{eyre, Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+
+/// Paths that are never served or listed, unless the project config says otherwise
+pub static DEFAULT_HIDDEN: &[&str] = &[".*", "_redirects", "_headers"];
+
+/// Whether symlinks in the root dir are served
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkPolicy {
+    /// Only symlinks that point to a file inside the root dir
+    #[default]
+    Inside,
+    /// Any symlink, even when it points outside the root dir
+    Follow,
+    /// No symlinks at all
+    Deny,
+}
+
+impl Display for SymlinkPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Inside => write!(f, "inside"),
+            Self::Follow => write!(f, "follow"),
+            Self::Deny => write!(f, "deny"),
+        }
+    }
+}
+
+impl FromStr for SymlinkPolicy {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "inside" => Ok(Self::Inside),
+            "follow" => Ok(Self::Follow),
+            "deny" => Ok(Self::Deny),
+            _ => Err(eyre!(
+                "Unknown symlink policy {s}, expected one of: inside, follow, deny"
+            )),
+        }
+    }
+}
+
+/// Why a path can't be served
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Denied {
+    /// Matches the hidden list, responds as if the file doesn't exist
+    Hidden,
+    /// `..` segments or a symlink that leads out of the root dir
+    OutsideRoot,
+    Symlink,
+}
+
+impl Display for Denied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Hidden => write!(f, "hidden path"),
+            Self::OutsideRoot => write!(f, "outside of the root dir"),
+            Self::Symlink => write!(f, "symlinks are not allowed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AccessPolicy {
+    hidden: GlobSet,
+    symlinks: SymlinkPolicy,
+}
+
+impl AccessPolicy {
+    /// `hidden` patterns are matched against each part of the path and against the whole path
+    /// relative to the root, ex: `.*` hides all dotfiles, `drafts/*.html` hides html in drafts
+    pub fn new(hidden: &[String], symlinks: SymlinkPolicy) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+
+        for pattern in hidden {
+            let glob = Glob::new(pattern)
+                .wrap_err_with(|| format!("Invalid pattern in server.hidden: {pattern}"))?;
+
+            builder.add(glob);
+        }
+
+        Ok(Self {
+            hidden: builder.build()?,
+            symlinks,
+        })
+    }
+
+    /// Checks a path relative to the root dir before it is resolved, this doesn't touch the
+    /// file system so a hidden file is never revealed by a redirect
+    pub fn check_relative(&self, relative: &Path) -> Result<(), Denied> {
+        let escapes = relative
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_)));
+
+        if escapes {
+            return Err(Denied::OutsideRoot);
+        }
+
+        if self.is_hidden(relative) {
+            return Err(Denied::Hidden);
+        }
+
+        Ok(())
+    }
+
+    /// Checks a resolved file or directory inside the canonical root dir
+    pub fn check(&self, root: &Path, path: &Path) -> Result<(), Denied> {
+        let relative = path.strip_prefix(root).map_err(|_| Denied::OutsideRoot)?;
+        self.check_relative(relative)?;
+
+        if self.symlinks == SymlinkPolicy::Follow {
+            return Ok(());
+        }
+
+        if self.symlinks == SymlinkPolicy::Deny && has_symlink(root, relative) {
+            return Err(Denied::Symlink);
+        }
+
+        let canonical = std::fs::canonicalize(path).map_err(|_| Denied::OutsideRoot)?;
+
+        if !canonical.starts_with(root) {
+            return Err(Denied::OutsideRoot);
+        }
+
+        // a symlink inside the root can still point at a hidden file, ex: `env -> .env`
+        if self.is_hidden(canonical.strip_prefix(root).unwrap_or(&canonical)) {
+            return Err(Denied::Hidden);
+        }
+
+        Ok(())
+    }
+
+    fn is_hidden(&self, relative: &Path) -> bool {
+        self.hidden.is_match(relative)
+            || relative
+                .components()
+                .any(|component| self.hidden.is_match(component.as_os_str()))
+    }
+}
+
+fn has_symlink(root: &Path, relative: &Path) -> bool {
+    let mut path = root.to_path_buf();
+
+    relative.components().any(|component| {
+        path.push(component);
+
+        path.symlink_metadata()
+            .map(|metadata| metadata.file_type().is_symlink())
+            .unwrap_or(false)
+    })
+}
@@ -0,0 +1,122 @@
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+
+use color_eyre::Help;
+use eyre::{eyre, Result};
+use qrcode::{render::unicode, QrCode};
+
+/// Parses the host to bind to, an ip address like `0.0.0.0` or `::`, or a host name
+pub fn resolve_host(host: &str) -> Result<IpAddr> {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    if let Ok(ip) = host.parse() {
+        return Ok(ip);
+    }
+
+    (host, 0)
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .map(|addr| addr.ip())
+        .ok_or_else(|| eyre!("Unable to resolve host: {host}"))
+        .suggestion("Use an ip address, ex: `--host 0.0.0.0` to listen on all interfaces")
+}
+
+/// Urls the server can be reached at, split into this machine and the local network
+pub struct Urls {
+    pub local: String,
+    pub network: Vec<String>,
+}
+
+impl Urls {
+    pub fn new(addr: SocketAddr) -> Self {
+        let port = addr.port();
+        let ip = addr.ip();
+
+        let local = if ip.is_loopback() || ip.is_unspecified() {
+            format!("http://localhost:{port}")
+        } else {
+            url(ip, port)
+        };
+
+        let network = if ip.is_unspecified() {
+            lan_addresses(ip)
+                .into_iter()
+                .map(|ip| url(ip, port))
+                .collect()
+        } else if ip.is_loopback() {
+            vec![]
+        } else {
+            vec![url(ip, port)]
+        };
+
+        Self { local, network }
+    }
+
+    /// Prints the urls, with a qr code for the first network url so it can be opened on a phone
+    pub fn print(&self) {
+        println!();
+        println!("  Local:   {}", self.local);
+
+        if self.network.is_empty() {
+            println!("  Network: use `--host 0.0.0.0` to expose the server on your network");
+            println!();
+            return;
+        }
+
+        for url in &self.network {
+            println!("  Network: {url}");
+        }
+
+        if let Some(qr) = self.network.first().and_then(|url| qr_code(url)) {
+            println!();
+            println!("{qr}");
+        }
+
+        println!();
+    }
+}
+
+fn url(ip: IpAddr, port: u16) -> String {
+    match ip {
+        IpAddr::V4(ip) => format!("http://{ip}:{port}"),
+        IpAddr::V6(ip) => format!("http://[{ip}]:{port}"),
+    }
+}
+
+// addresses of the network interfaces, a server bound to `0.0.0.0` is only reachable over ipv4
+// while one bound to `::` accepts both
+fn lan_addresses(bound: IpAddr) -> Vec<IpAddr> {
+    let Ok(interfaces) = if_addrs::get_if_addrs() else {
+        return vec![];
+    };
+
+    let mut addresses = interfaces
+        .into_iter()
+        .map(|interface| interface.ip())
+        .filter(|ip| !ip.is_loopback())
+        .filter(|ip| match ip {
+            IpAddr::V4(_) => true,
+            // link local addresses need a zone id which browsers don't support
+            IpAddr::V6(ip) => bound.is_ipv6() && !ip.is_unicast_link_local(),
+        })
+        .collect::<Vec<_>>();
+
+    // ipv4 first, it's what people type into their phone
+    addresses.sort_by_key(|ip| ip.is_ipv6());
+    addresses.dedup();
+
+    addresses
+}
+
+// drawn for a dark terminal, the dark modules are left blank and the light ones filled in
+fn qr_code(url: &str) -> Option<String> {
+    let code = QrCode::new(url).ok()?;
+
+    Some(
+        code.render::<unicode::Dense1x2>()
+            .dark_color(unicode::Dense1x2::Light)
+            .light_color(unicode::Dense1x2::Dark)
+            .quiet_zone(true)
+            .build(),
+    )
+}
@@ -0,0 +1,96 @@
+use std::{fmt::Display, str::FromStr};
+
+use axum::{
+    extract::Request,
+    http::{self, header},
+    middleware::Next,
+    response::Response,
+};
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use tower_http::set_header::SetResponseHeaderLayer;
+
+use crate::build::fingerprint::HASH_LEN;
+
+/// How the browser is told to cache responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheMode {
+    /// Never cache, every change shows up on the next load
+    #[default]
+    None,
+    /// Cache but check with the server every time, unchanged files get a `304 Not Modified`
+    /// using their `ETag` or `Last-Modified`, like most static hosts
+    Revalidate,
+    /// Fingerprinted files, ex: `app.3f9a1c2b.css`, are cached for a year without checking
+    /// again, everything else revalidates. How a production build should be served
+    Immutable,
+}
+
+impl Display for CacheMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Revalidate => write!(f, "revalidate"),
+            Self::Immutable => write!(f, "immutable"),
+        }
+    }
+}
+
+impl FromStr for CacheMode {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "revalidate" => Ok(Self::Revalidate),
+            "immutable" => Ok(Self::Immutable),
+            _ => Err(eyre!(
+                "Unknown cache mode {s}, expected one of: none, revalidate, immutable"
+            )),
+        }
+    }
+}
+
+pub fn revalidate_layer() -> SetResponseHeaderLayer<http::HeaderValue> {
+    SetResponseHeaderLayer::overriding(
+        http::header::CACHE_CONTROL,
+        http::HeaderValue::from_static("no-cache"),
+    )
+}
+
+/// Sets `Cache-Control` for `CacheMode::Immutable`, headers set in `_headers` are kept
+pub async fn immutable(req: Request, next: Next) -> Response {
+    let fingerprinted = is_fingerprinted(req.uri().path());
+    let mut response = next.run(req).await;
+
+    if response.headers().contains_key(header::CACHE_CONTROL) {
+        return response;
+    }
+
+    // a missing file could exist after the next deploy, so only found files are cached
+    let value = if fingerprinted && response.status().is_success() {
+        "public, max-age=31536000, immutable"
+    } else {
+        "no-cache"
+    };
+
+    response
+        .headers_mut()
+        .insert(header::CACHE_CONTROL, http::HeaderValue::from_static(value));
+
+    response
+}
+
+// `app.3f9a1c2b.css`, the name `easywind build` gives files
+fn is_fingerprinted(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or_default();
+    let mut parts = name.rsplit('.');
+
+    let (Some(_extension), Some(hash), Some(_stem)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return false;
+    };
+
+    hash.len() == HASH_LEN && hash.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
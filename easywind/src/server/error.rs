@@ -5,6 +5,7 @@ use axum::{
     response::{IntoResponse, Response},
 };
 use log::error;
+use serde::Serialize;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -24,21 +25,72 @@ pub enum Error {
     #[error("Unable to get metadata for file, path: {0}")]
     FileMetadataError(PathBuf),
 
-    #[error("File larger than metadata reported, path: {0}")]
-    FileReadBufferOverflow(PathBuf),
+    #[error("Unable to reach proxy backend at {0}: {1}")]
+    ProxyUnavailable(String, String),
+
+    #[error("File not found: {0}")]
+    NotFound(String),
+
+    #[error("Not allowed to access: {0}")]
+    Forbidden(String),
+
+    #[error("Unable to render page: {0}")]
+    Render(#[from] crate::render::RenderError),
 
     #[error(transparent)]
     Unknown(#[from] eyre::Report),
 }
 
+impl Error {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::ProxyUnavailable(..) => StatusCode::BAD_GATEWAY,
+            Self::IoError(error) => match error.kind() {
+                std::io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+                std::io::ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Added to the extensions of every error response, so the error page middleware can render
+/// it as html or json
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorInfo {
+    pub status: u16,
+    pub title: String,
+    pub message: String,
+    /// Extra context shown below the message, ex: the template line that failed to render
+    pub detail: Option<String>,
+}
+
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
-        error!("{:?}", self);
+        let status = self.status();
+
+        if status.is_server_error() {
+            error!("{:?}", self);
+        }
+
+        let detail = match &self {
+            Self::Render(error) => error.detail.clone(),
+            _ => None,
+        };
+
+        let info = ErrorInfo {
+            status: status.as_u16(),
+            title: status.canonical_reason().unwrap_or("Error").to_string(),
+            message: self.to_string(),
+            detail,
+        };
+
+        let mut response = (status, info.message.clone()).into_response();
+        response.extensions_mut().insert(info);
 
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Something went wrong: {:?}", self),
-        )
-            .into_response()
+        response
     }
 }
@@ -0,0 +1,79 @@
+use std::path::PathBuf;
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+
+use super::error::ErrorInfo;
+use crate::template::{TemplateName, TEMPLATE};
+
+/// Project `404.html` served for missing files, like static hosts do
+pub static NOT_FOUND_PAGE: &str = "404.html";
+
+#[derive(Debug, Clone, Default)]
+pub struct ErrorPages {
+    /// Where to look for a `404.html`, not set when proxying
+    pub root_dir: Option<PathBuf>,
+}
+
+/// Replaces the plain text body of error responses with the project's `404.html`, the built in
+/// error page, or json when the client asks for it
+pub async fn render(State(pages): State<ErrorPages>, req: Request, next: Next) -> Response {
+    let wants_json = wants_json(req.headers());
+    let response = next.run(req).await;
+
+    let Some(info) = response.extensions().get::<ErrorInfo>().cloned() else {
+        return response;
+    };
+
+    let (mut parts, _) = response.into_parts();
+
+    let (content_type, body) = if wants_json {
+        let json = serde_json::json!({ "error": info });
+        ("application/json", json.to_string())
+    } else if let Some(page) = not_found_page(&pages, parts.status).await {
+        ("text/html; charset=utf-8", page)
+    } else {
+        let ctx = minijinja::context! {
+            status => info.status,
+            title => info.title,
+            message => info.message,
+            detail => info.detail,
+        };
+
+        (
+            "text/html; charset=utf-8",
+            TEMPLATE.render(TemplateName::Error, &ctx),
+        )
+    };
+
+    parts
+        .headers
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    parts
+        .headers
+        .insert(header::CONTENT_LENGTH, HeaderValue::from(body.len()));
+
+    Response::from_parts(parts, Body::from(body))
+}
+
+async fn not_found_page(pages: &ErrorPages, status: StatusCode) -> Option<String> {
+    if status != StatusCode::NOT_FOUND {
+        return None;
+    }
+
+    let path = pages.root_dir.as_ref()?.join(NOT_FOUND_PAGE);
+    tokio::fs::read_to_string(path).await.ok()
+}
+
+// fetch and xhr requests usually ask for json, browsers always ask for html
+fn wants_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| accept.contains("json") && !accept.contains("text/html"))
+}
@@ -0,0 +1,261 @@
+use std::{
+    io::SeekFrom,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
+
+use super::{content_type, error::Error};
+
+/// Encodings of precompressed siblings, in order of preference, ex: `app.css.br`
+static PRECOMPRESSED: &[(&str, &str)] = &[("br", "br"), ("gzip", "gz")];
+
+/// Streams a file from disk, supports `HEAD`, a single byte range and conditional requests
+/// with `If-None-Match` and `If-Modified-Since`. With `precompressed` set, a `.br` or `.gz`
+/// sibling is sent instead when the browser accepts it
+pub async fn serve_file(
+    path: &Path,
+    method: &Method,
+    headers: &HeaderMap,
+    precompressed: bool,
+) -> Result<Response, Error> {
+    let sibling = if precompressed && !is_html(path) {
+        precompressed_sibling(path, headers).await
+    } else {
+        None
+    };
+
+    let file_path = sibling
+        .as_ref()
+        .map_or(path, |(sibling, _)| sibling.as_path());
+
+    let mut file = match tokio::fs::File::open(file_path).await {
+        Ok(file) => file,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            return Err(Error::NotFound(path.display().to_string()))
+        }
+        Err(error) => return Err(error.into()),
+    };
+
+    let metadata = file
+        .metadata()
+        .await
+        .map_err(|_| Error::FileMetadataError(file_path.to_path_buf()))?;
+
+    let len = metadata.len();
+    let modified = metadata.modified().ok();
+    let etag = etag(len, modified);
+
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(header::ETAG, header_value(&etag));
+    response_headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+
+    // the compression layer sets `Vary` on everything else it could compress
+    if let Some((_, encoding)) = sibling {
+        response_headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding));
+        response_headers.insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+    }
+
+    if let Some(modified) = modified {
+        response_headers.insert(
+            header::LAST_MODIFIED,
+            header_value(&httpdate::fmt_http_date(modified)),
+        );
+    }
+
+    if is_not_modified(headers, &etag, modified) {
+        return Ok((StatusCode::NOT_MODIFIED, response_headers).into_response());
+    }
+
+    response_headers.insert(header::CONTENT_TYPE, content_type(path));
+
+    let range = match requested_range(headers, &etag, modified) {
+        Some(range) => match parse_range(range, len) {
+            Some(range) => Some(range),
+            None => {
+                response_headers.insert(
+                    header::CONTENT_RANGE,
+                    header_value(&format!("bytes */{len}")),
+                );
+                return Ok((StatusCode::RANGE_NOT_SATISFIABLE, response_headers).into_response());
+            }
+        },
+        None => None,
+    };
+
+    let (status, start, length) = match range {
+        Some(range) => {
+            let (start, end) = (*range.start(), *range.end());
+            response_headers.insert(
+                header::CONTENT_RANGE,
+                header_value(&format!("bytes {start}-{end}/{len}")),
+            );
+
+            (StatusCode::PARTIAL_CONTENT, start, end - start + 1)
+        }
+        None => (StatusCode::OK, 0, len),
+    };
+
+    response_headers.insert(header::CONTENT_LENGTH, header_value(&length.to_string()));
+
+    if method == Method::HEAD {
+        return Ok((status, response_headers).into_response());
+    }
+
+    if start > 0 {
+        file.seek(SeekFrom::Start(start)).await?;
+    }
+
+    let body = Body::from_stream(ReaderStream::new(file.take(length)));
+
+    Ok((status, response_headers, body).into_response())
+}
+
+// a sibling older than the file is left over from a previous build and would serve stale content
+async fn precompressed_sibling(
+    path: &Path,
+    headers: &HeaderMap,
+) -> Option<(PathBuf, &'static str)> {
+    let modified = tokio::fs::metadata(path).await.ok()?.modified().ok()?;
+
+    for (encoding, extension) in PRECOMPRESSED {
+        if !accepts_encoding(headers, encoding) {
+            continue;
+        }
+
+        let mut sibling = path.as_os_str().to_owned();
+        sibling.push(".");
+        sibling.push(extension);
+        let sibling = PathBuf::from(sibling);
+
+        let is_fresh = tokio::fs::metadata(&sibling)
+            .await
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|sibling_modified| sibling_modified >= modified);
+
+        if is_fresh {
+            return Some((sibling, encoding));
+        }
+    }
+
+    None
+}
+
+fn accepts_encoding(headers: &HeaderMap, encoding: &str) -> bool {
+    let Some(accept_encoding) = header_str(headers, header::ACCEPT_ENCODING) else {
+        return false;
+    };
+
+    accept_encoding.split(',').any(|value| {
+        let mut parts = value.split(';');
+        let name = parts.next().unwrap_or_default().trim();
+
+        let quality = parts
+            .find_map(|part| part.trim().strip_prefix("q="))
+            .and_then(|quality| quality.parse::<f32>().ok())
+            .unwrap_or(1.0);
+
+        name.eq_ignore_ascii_case(encoding) && quality > 0.0
+    })
+}
+
+// html is compressed on the fly, after the livereload script is added
+fn is_html(path: &Path) -> bool {
+    mime_guess::from_path(path)
+        .first()
+        .is_some_and(|mime| mime.essence_str() == "text/html")
+}
+
+// changes whenever the file is written to, without having to hash the contents
+fn etag(len: u64, modified: Option<SystemTime>) -> String {
+    let modified = modified
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|modified| modified.as_nanos())
+        .unwrap_or_default();
+
+    format!("\"{len:x}-{modified:x}\"")
+}
+
+fn header_value(value: &str) -> HeaderValue {
+    HeaderValue::from_str(value).expect("valid header value")
+}
+
+// `If-None-Match` wins over `If-Modified-Since` when both are sent
+fn is_not_modified(headers: &HeaderMap, etag: &str, modified: Option<SystemTime>) -> bool {
+    if let Some(if_none_match) = header_str(headers, header::IF_NONE_MATCH) {
+        return if_none_match
+            .split(',')
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag == "*" || tag == etag);
+    }
+
+    match (header_str(headers, header::IF_MODIFIED_SINCE), modified) {
+        (Some(since), Some(modified)) => httpdate::parse_http_date(since)
+            .map(|since| truncate_to_seconds(modified) <= since)
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+// the byte range spec, ex: `0-99`, unless `If-Range` says the client has an outdated copy.
+// other units and multiple ranges are ignored and the whole file is sent, which the spec allows
+fn requested_range<'a>(
+    headers: &'a HeaderMap,
+    etag: &str,
+    modified: Option<SystemTime>,
+) -> Option<&'a str> {
+    let spec = header_str(headers, header::RANGE)?
+        .strip_prefix("bytes=")
+        .filter(|spec| !spec.contains(','))?;
+
+    let Some(if_range) = header_str(headers, header::IF_RANGE) else {
+        return Some(spec);
+    };
+
+    let still_valid = if if_range.starts_with('"') {
+        if_range == etag
+    } else {
+        match (httpdate::parse_http_date(if_range), modified) {
+            (Ok(date), Some(modified)) => truncate_to_seconds(modified) == date,
+            _ => false,
+        }
+    };
+
+    still_valid.then_some(spec)
+}
+
+/// Parses a byte range spec, `0-99`, `100-` or `-100` for the last 100 bytes, `None` when
+/// it's invalid or starts past the end of the file
+fn parse_range(spec: &str, len: u64) -> Option<RangeInclusive<u64>> {
+    let (start, end) = spec.trim().split_once('-')?;
+    let last = len.checked_sub(1)?;
+
+    let (start, end) = match (start.parse::<u64>(), end.parse::<u64>()) {
+        (Err(_), Ok(suffix)) if start.is_empty() && suffix > 0 => {
+            (len.saturating_sub(suffix), last)
+        }
+        (Ok(start), Err(_)) if end.is_empty() => (start, last),
+        (Ok(start), Ok(end)) if start <= end => (start, end.min(last)),
+        _ => return None,
+    };
+
+    (start <= last).then_some(start..=end)
+}
+
+fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+fn truncate_to_seconds(time: SystemTime) -> SystemTime {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| UNIX_EPOCH + std::time::Duration::from_secs(duration.as_secs()))
+        .unwrap_or(time)
+}
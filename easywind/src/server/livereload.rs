@@ -0,0 +1,205 @@
+use std::{
+    convert::Infallible,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::get,
+    Router,
+};
+use futures::{stream, Stream, StreamExt};
+use log::error;
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::tailwind::output::{BuildError, BuildResult};
+
+static LIVERELOAD_JS: &str = include_str!("../../static/livereload.js");
+static EVENTS_PATH: &str = "/__internal_only_easywind_livereload__";
+static SCRIPT_PATH: &str = "/__internal_only_easywind_livereload__.js";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum Message {
+    Reload,
+    /// Url paths of the stylesheets that changed, ex: `/dist/app.css`
+    CssSwap {
+        paths: Vec<String>,
+    },
+    BuildError {
+        error: BuildError,
+    },
+    BuildOk,
+}
+
+/// Sends reloads and tailwind build results to every connected page
+#[derive(Debug, Clone)]
+pub struct Reloader {
+    sender: broadcast::Sender<Message>,
+    // pages that load while the build is broken still need to show the error
+    build_error: Arc<Mutex<Option<BuildError>>>,
+}
+
+impl Default for Reloader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reloader {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(16);
+
+        Self {
+            sender,
+            build_error: Arc::default(),
+        }
+    }
+
+    pub fn reload(&self) {
+        let _ = self.sender.send(Message::Reload);
+    }
+
+    /// Swaps the stylesheets in place, pages keep their scroll position and state
+    pub fn swap_css(&self, paths: Vec<String>) {
+        let _ = self.sender.send(Message::CssSwap { paths });
+    }
+
+    pub fn build_result(&self, result: BuildResult) {
+        let message = match result {
+            BuildResult::Success => {
+                *self.build_error.lock().expect("lock poisoned") = None;
+                Message::BuildOk
+            }
+            BuildResult::Failed(error) => {
+                *self.build_error.lock().expect("lock poisoned") = Some(error.clone());
+                Message::BuildError { error }
+            }
+        };
+
+        let _ = self.sender.send(message);
+    }
+
+    fn events(&self) -> impl Stream<Item = Message> {
+        let current = self
+            .build_error
+            .lock()
+            .expect("lock poisoned")
+            .clone()
+            .map(|error| Message::BuildError { error });
+
+        let updates = stream::unfold(self.sender.subscribe(), |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(message) => return Some((message, receiver)),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        });
+
+        stream::iter(current).chain(updates)
+    }
+}
+
+/// Routes for the livereload script and the event stream it listens to
+pub fn routes<S>(reloader: Reloader) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route(SCRIPT_PATH, get(script))
+        .route(EVENTS_PATH, get(move || events(reloader.clone())))
+}
+
+async fn script() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/javascript")], LIVERELOAD_JS)
+}
+
+async fn events(reloader: Reloader) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let events = reloader.events().map(|message| {
+        let data = serde_json::to_string(&message).expect("message is valid json");
+        Ok(Event::default().data(data))
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Adds the livereload script to html responses, compressed responses are left alone
+pub async fn inject(req: Request, next: Next) -> Response {
+    let is_head = req.method() == Method::HEAD;
+    let response = next.run(req).await;
+
+    let is_html = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/html"));
+
+    // a byte range of the page can't have the script added without breaking the range
+    let is_partial = response.status() == StatusCode::PARTIAL_CONTENT;
+
+    if !is_html || is_partial || response.headers().contains_key(header::CONTENT_ENCODING) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+
+    // the script is always added once, so the length of the page is known without the body
+    if is_head {
+        let length = parts
+            .headers
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok()?.parse::<usize>().ok());
+
+        if let Some(length) = length {
+            parts.headers.insert(
+                header::CONTENT_LENGTH,
+                HeaderValue::from(length + script_tag().len()),
+            );
+        }
+
+        return Response::from_parts(parts, body);
+    }
+
+    let html = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(html) => html,
+        Err(error) => {
+            error!("Unable to read html to inject livereload: {error}");
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+
+    // works on the bytes and keeps the content type, pages from a proxied backend aren't
+    // always utf-8
+    let html = inject_script(&html);
+    parts.headers.remove(header::CONTENT_LENGTH);
+
+    Response::from_parts(parts, Body::from(html))
+}
+
+fn script_tag() -> String {
+    format!(r#"<script src="{SCRIPT_PATH}"></script>"#)
+}
+
+fn inject_script(html: &[u8]) -> Vec<u8> {
+    let script = script_tag();
+    let index = html
+        .windows(b"</body>".len())
+        .rposition(|window| window == b"</body>")
+        .unwrap_or(html.len());
+
+    let mut injected = Vec::with_capacity(html.len() + script.len());
+    injected.extend_from_slice(&html[..index]);
+    injected.extend_from_slice(script.as_bytes());
+    injected.extend_from_slice(&html[index..]);
+    injected
+}
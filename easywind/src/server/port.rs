@@ -1,24 +1,37 @@
-use std::net::TcpListener;
+use std::net::{IpAddr, Ipv4Addr, TcpListener};
 
+use color_eyre::Help;
+use eyre::{eyre, Result};
 use log::warn;
 
-pub fn default_or_available(port: u16) -> Option<u16> {
-    if is_available(port) {
-        Some(port)
-    } else {
-        warn!("Port {port} is not available, finding new port");
-        get_available()
+/// The port to use, if it is taken another one is picked unless `strict` is set
+pub fn default_or_available(ip: IpAddr, port: u16, strict: bool) -> Result<u16> {
+    if is_available(ip, port) {
+        return Ok(port);
     }
+
+    if strict {
+        return Err(eyre!("Port {port} is not available"))
+            .suggestion("Stop the process using it, or pick another port with `--port`")
+            .suggestion("Remove `--strict-port` to use the next available port");
+    }
+
+    warn!("Port {port} is not available, finding new port");
+    get_available(ip).ok_or_else(|| eyre!("Unable to find an available port"))
 }
 
-pub fn get_available() -> Option<u16> {
+pub fn get_available(ip: IpAddr) -> Option<u16> {
     (3500..3999)
         .chain(4001..4999)
         .chain(5001..5999)
         .chain(8001..8999)
-        .find(|port| is_available(*port))
+        .find(|port| is_available(ip, *port))
 }
 
-pub fn is_available(port: u16) -> bool {
-    TcpListener::bind(("127.0.0.1", port)).is_ok() && TcpListener::bind(("0.0.0.0", port)).is_ok()
+/// A server bound to all interfaces also conflicts with one bound to localhost
+pub fn is_available(ip: IpAddr, port: u16) -> bool {
+    let loopback_free =
+        !ip.is_unspecified() || TcpListener::bind((Ipv4Addr::LOCALHOST, port)).is_ok();
+
+    loopback_free && TcpListener::bind((ip, port)).is_ok()
 }
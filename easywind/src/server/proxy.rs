@@ -0,0 +1,184 @@
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{
+        header,
+        uri::{PathAndQuery, Scheme},
+        HeaderName, HeaderValue, StatusCode, Uri, Version,
+    },
+    response::Response,
+};
+use eyre::{eyre, Result};
+use hyper_util::{
+    client::legacy::{connect::HttpConnector, Client},
+    rt::{TokioExecutor, TokioIo},
+};
+use log::{error, info};
+
+use super::error::Error;
+
+// headers that only apply to a single connection, these must not be forwarded
+static HOP_BY_HOP_HEADERS: &[HeaderName] = &[
+    header::CONNECTION,
+    header::PROXY_AUTHENTICATE,
+    header::PROXY_AUTHORIZATION,
+    header::TE,
+    header::TRAILER,
+    header::TRANSFER_ENCODING,
+    header::UPGRADE,
+];
+
+#[derive(Clone)]
+pub struct Proxy {
+    target: Uri,
+    client: Client<HttpConnector, Body>,
+}
+
+impl Proxy {
+    pub fn new(target: &str) -> Result<Self> {
+        let target: Uri = target
+            .parse()
+            .map_err(|error| eyre!("Invalid proxy url {target}: {error}"))?;
+
+        if target.scheme() != Some(&Scheme::HTTP) {
+            return Err(eyre!(
+                "Invalid proxy url {target}: only http:// backends are supported"
+            ));
+        }
+
+        if target.authority().is_none() {
+            return Err(eyre!("Invalid proxy url {target}: missing host"));
+        }
+
+        let client = Client::builder(TokioExecutor::new()).build(HttpConnector::new());
+
+        Ok(Self { target, client })
+    }
+
+    pub fn target(&self) -> &Uri {
+        &self.target
+    }
+
+    fn backend_uri(&self, uri: &Uri) -> Result<Uri, Error> {
+        let base_path = self.target.path().trim_end_matches('/');
+        let path_and_query = uri
+            .path_and_query()
+            .map(PathAndQuery::as_str)
+            .unwrap_or("/");
+
+        let uri = Uri::builder()
+            .scheme(Scheme::HTTP)
+            .authority(
+                self.target
+                    .authority()
+                    .expect("checked in Proxy::new")
+                    .clone(),
+            )
+            .path_and_query(format!("{base_path}{path_and_query}"))
+            .build()?;
+
+        Ok(uri)
+    }
+}
+
+/// Forwards a request to the backend and streams the response back,
+/// websocket upgrades are tunneled through to the backend
+pub async fn forward(State(proxy): State<Proxy>, mut req: Request) -> Result<Response, Error> {
+    info!("PROXY {} {}", req.method(), req.uri());
+
+    let is_upgrade = is_upgrade_request(&req);
+    let client_upgrade = is_upgrade.then(|| hyper::upgrade::on(&mut req));
+
+    let backend_uri = proxy.backend_uri(req.uri())?;
+    let (mut parts, body) = req.into_parts();
+    let client_version = parts.version;
+
+    let original_host = parts.headers.get(header::HOST).cloned();
+
+    if !is_upgrade {
+        remove_hop_by_hop_headers(&mut parts.headers);
+    }
+
+    // livereload can only be injected into uncompressed html
+    parts.headers.remove(header::ACCEPT_ENCODING);
+
+    if let Some(host) = original_host {
+        parts
+            .headers
+            .insert(HeaderName::from_static("x-forwarded-host"), host);
+    }
+
+    parts.headers.insert(
+        HeaderName::from_static("x-forwarded-proto"),
+        HeaderValue::from_static("http"),
+    );
+
+    if let Some(authority) = backend_uri.authority() {
+        parts.headers.insert(
+            header::HOST,
+            HeaderValue::from_str(authority.as_str()).map_err(axum::http::Error::from)?,
+        );
+    }
+
+    parts.uri = backend_uri;
+    parts.version = Version::HTTP_11;
+    let backend_req = Request::from_parts(parts, body);
+
+    let mut response =
+        proxy.client.request(backend_req).await.map_err(|error| {
+            Error::ProxyUnavailable(proxy.target.to_string(), error.to_string())
+        })?;
+
+    if response.status() == StatusCode::SWITCHING_PROTOCOLS {
+        if let Some(client_upgrade) = client_upgrade {
+            let backend_upgrade = hyper::upgrade::on(&mut response);
+            tokio::spawn(async move {
+                if let Err(error) = tunnel(client_upgrade, backend_upgrade).await {
+                    error!("Proxied websocket connection closed with error: {error:?}");
+                }
+            });
+        }
+    } else {
+        remove_hop_by_hop_headers(response.headers_mut());
+    }
+
+    *response.version_mut() = client_version;
+
+    Ok(response.map(Body::new))
+}
+
+async fn tunnel(
+    client_upgrade: hyper::upgrade::OnUpgrade,
+    backend_upgrade: hyper::upgrade::OnUpgrade,
+) -> Result<()> {
+    let (client, backend) = tokio::try_join!(client_upgrade, backend_upgrade)?;
+
+    let mut client = TokioIo::new(client);
+    let mut backend = TokioIo::new(backend);
+
+    tokio::io::copy_bidirectional(&mut client, &mut backend).await?;
+
+    Ok(())
+}
+
+fn is_upgrade_request(req: &Request) -> bool {
+    req.headers()
+        .get(header::CONNECTION)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value
+                .split(',')
+                .any(|token| token.trim().eq_ignore_ascii_case("upgrade"))
+        })
+        .unwrap_or(false)
+        && req.headers().contains_key(header::UPGRADE)
+}
+
+fn remove_hop_by_hop_headers(headers: &mut axum::http::HeaderMap) {
+    for name in HOP_BY_HOP_HEADERS {
+        headers.remove(name);
+    }
+
+    headers.remove("keep-alive");
+    headers.remove("proxy-connection");
+}
@@ -1,34 +1,162 @@
-use log::{error, info};
-use notify_debouncer_mini::DebounceEventResult;
-use tower_livereload::Reloader;
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use super::livereload::Reloader;
+use log::info;
 
 static FILE_TYPES: &[&str] = &[
     "html", "css", "js", "jinja", "md", "toml", "js", "ts", "tsx", "j2", "heex", "sface", "eex",
     "leex",
 ];
 
-pub(crate) fn handle_reload(event: DebounceEventResult, reloader: &Reloader) -> eyre::Result<()> {
-    match event {
-        Ok(events) => events.iter().for_each(|event| {
-            let path = &event.path;
+/// Reloads the page or swaps its stylesheets for the changed files. The tailwind input and the
+/// css it imports are left out, tailwind rebuilds the output and that change swaps the css
+pub(crate) fn handle_reload(
+    changed: Vec<PathBuf>,
+    reloader: &Reloader,
+    root_dir: &Path,
+    tailwind_input: Option<&Path>,
+) {
+    let sources = tailwind_input.map(source_stylesheets).unwrap_or_default();
+
+    let changed = changed
+        .iter()
+        .filter(|path| !path.is_dir())
+        .filter(|path| !sources.contains(*path))
+        // only reload files that are in watcher file types
+        .filter(|path| {
+            path.extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| FILE_TYPES.contains(&extension))
+        })
+        .collect::<Vec<_>>();
+
+    if changed.is_empty() {
+        return;
+    }
+
+    // a stylesheet can be swapped in place, anything else needs a full reload
+    if changed.iter().all(|path| is_css(path)) {
+        let urls = changed
+            .iter()
+            .map(|path| url_path(path, root_dir))
+            .collect::<Vec<_>>();
+
+        info!("Updating css {} ...", urls.join(", "));
+        reloader.swap_css(urls);
+    } else {
+        for path in &changed {
+            info!("Reloading {} ...", path.to_string_lossy());
+        }
+
+        reloader.reload();
+    }
+}
+
+// the input and every local file it imports, the watcher reports canonical paths
+fn source_stylesheets(input: &Path) -> BTreeSet<PathBuf> {
+    let mut sources = BTreeSet::new();
+    let mut pending = vec![input.to_path_buf()];
+
+    while let Some(path) = pending.pop() {
+        let Ok(path) = std::fs::canonicalize(path) else {
+            continue;
+        };
+
+        if !sources.insert(path.clone()) {
+            continue;
+        }
+
+        let (Ok(css), Some(dir)) = (std::fs::read_to_string(&path), path.parent()) else {
+            continue;
+        };
+
+        // packages like `@import "tailwindcss"` don't resolve to a file and are skipped
+        pending.extend(css_imports(&css).into_iter().map(|import| dir.join(import)));
+    }
+
+    sources
+}
+
+// the urls of `@import "..."` and `@import url(...)`
+fn css_imports(css: &str) -> Vec<&str> {
+    css.split("@import")
+        .skip(1)
+        .filter_map(|rest| {
+            let rest = rest.trim_start();
+            let rest = rest.strip_prefix("url(").unwrap_or(rest).trim_start();
+            let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+
+            rest[1..].split(quote).next()
+        })
+        .collect()
+}
 
-            if path.is_dir() {
-                return;
-            }
+fn is_css(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "css")
+}
 
-            let Some(extention) = path.extension() else {
-                return;
-            };
+// the url the file is served at, ex: `/dist/app.css`
+pub(crate) fn url_path(path: &Path, root_dir: &Path) -> String {
+    let relative = path.strip_prefix(root_dir).ok().or_else(|| {
+        path.strip_prefix(std::fs::canonicalize(root_dir).ok()?)
+            .ok()
+    });
 
-            // only reload files that are in watcher file types
-            if FILE_TYPES.contains(&extention.to_str().unwrap_or_default()) {
-                info!("Reloading {} ...", event.path.to_string_lossy());
-                reloader.reload()
-            }
-        }),
+    match relative {
+        Some(relative) => {
+            let components = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>();
 
-        Err(errors) => error!("Error while watching for changes: {:?}", errors),
+            format!("/{}", components.join("/"))
+        }
+        None => path
+            .file_name()
+            .map(|name| format!("/{}", name.to_string_lossy()))
+            .unwrap_or_default(),
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(())
+    #[test]
+    fn finds_quoted_imports() {
+        let css = r#"@import "tailwindcss";
+@import './components.css' layer(components);
+@import url("theme/colors.css");
+.a { color: red; }"#;
+
+        assert_eq!(
+            css_imports(css),
+            ["tailwindcss", "./components.css", "theme/colors.css"]
+        );
+    }
+
+    #[test]
+    fn collects_the_input_and_its_local_imports() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+
+        std::fs::create_dir_all(root.join("src/theme")).unwrap();
+        std::fs::write(
+            root.join("src/app.css"),
+            "@import \"tailwindcss\";\n@import \"./theme/colors.css\";",
+        )
+        .unwrap();
+        std::fs::write(root.join("src/theme/colors.css"), "@import \"../app.css\";").unwrap();
+        std::fs::write(root.join("src/unused.css"), "").unwrap();
+
+        let sources = source_stylesheets(&root.join("src/app.css"));
+
+        assert_eq!(
+            sources.into_iter().collect::<Vec<_>>(),
+            [root.join("src/app.css"), root.join("src/theme/colors.css")]
+        );
+    }
 }
@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+
+use percent_encoding::percent_decode_str;
+
+/// What a request path maps to, following the same rules as static hosts like Netlify,
+/// Vercel and GitHub Pages
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolved {
+    File(PathBuf),
+    /// A directory without an `index.html`, shown as a listing
+    Directory(PathBuf),
+    /// The canonical url for the path, ex: `/blog` -> `/blog/`
+    Redirect(String),
+    NotFound,
+}
+
+/// Resolves a url path against the root dir:
+///
+/// - `/blog/` serves `blog/index.html`, or a listing if there is none
+/// - `/blog` redirects to `/blog/`
+/// - `/about` serves `about.html` when there is no `about` file
+/// - `/about/` and `/app.css/` redirect to `/about` and `/app.css`
+/// - a page template, ex: `about.html.j2`, is served like `about.html` would be
+pub fn resolve(root: &Path, url_path: &str) -> Resolved {
+    let target = root.join(relative_path(url_path));
+    let trailing_slash = url_path.ends_with('/');
+
+    if trailing_slash {
+        if target.is_dir() {
+            if let Some(index) = file_or_template(&target.join("index.html")) {
+                return Resolved::File(index);
+            }
+
+            return Resolved::Directory(target);
+        }
+
+        if file_or_template(&target).is_some() || with_html_extension(&target).is_some() {
+            return Resolved::Redirect(url_path.trim_end_matches('/').to_string());
+        }
+
+        return Resolved::NotFound;
+    }
+
+    if let Some(file) = file_or_template(&target) {
+        return Resolved::File(file);
+    }
+
+    if target.is_dir() {
+        return Resolved::Redirect(format!("{url_path}/"));
+    }
+
+    match with_html_extension(&target) {
+        Some(html) => Resolved::File(html),
+        None => Resolved::NotFound,
+    }
+}
+
+/// The decoded url path as a path relative to the root dir, `..` segments are kept so they can
+/// be rejected
+pub fn relative_path(url_path: &str) -> PathBuf {
+    let decoded = percent_decode_str(url_path).decode_utf8_lossy();
+
+    decoded
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+// `about` -> `about.html` or `about.html.j2`
+fn with_html_extension(target: &Path) -> Option<PathBuf> {
+    target.file_name()?;
+    file_or_template(&with_suffix(target, ".html"))
+}
+
+// `about.html` -> `about.html.j2` when there is no plain html file
+fn file_or_template(target: &Path) -> Option<PathBuf> {
+    if target.is_file() {
+        return Some(target.to_path_buf());
+    }
+
+    let is_html = target
+        .extension()
+        .is_some_and(|extension| extension == "html");
+    let template = with_suffix(target, crate::render::TEMPLATE_SUFFIX);
+
+    (is_html && template.is_file()).then_some(template)
+}
+
+fn with_suffix(target: &Path, suffix: &str) -> PathBuf {
+    let mut path = target.as_os_str().to_owned();
+    path.push(suffix);
+
+    PathBuf::from(path)
+}
@@ -0,0 +1,224 @@
+mod headers;
+mod pattern;
+mod redirects;
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use log::{error, info};
+
+use self::{
+    headers::HeaderRule,
+    redirects::{Action, Redirect},
+};
+use super::resolve::{self, Resolved};
+
+pub static REDIRECTS_FILE: &str = "_redirects";
+pub static HEADERS_FILE: &str = "_headers";
+
+/// Rules from the `_redirects` and `_headers` files in the root dir, the same files Netlify
+/// and Cloudflare Pages read
+#[derive(Debug, Clone, Default)]
+pub struct Rules {
+    redirects: Vec<Redirect>,
+    headers: Vec<HeaderRule>,
+}
+
+impl Rules {
+    fn load(root_dir: &Path) -> Self {
+        let read = |name: &str| std::fs::read_to_string(root_dir.join(name)).unwrap_or_default();
+
+        Self {
+            redirects: redirects::parse(&read(REDIRECTS_FILE)),
+            headers: headers::parse(&read(HEADERS_FILE)),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.redirects.is_empty() && self.headers.is_empty()
+    }
+
+    /// The first redirect rule that applies to the request
+    fn action(&self, root: &Path, uri: &Uri) -> Option<Action> {
+        let file_exists = || {
+            !matches!(
+                resolve::resolve(root, uri.path()),
+                Resolved::NotFound | Resolved::Directory(_)
+            )
+        };
+
+        self.redirects
+            .iter()
+            .find_map(|redirect| redirect.action(uri.path(), uri.query(), file_exists))
+    }
+
+    /// Headers from every block that matches the path, values for the same header are joined
+    fn headers(&self, path: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        for (name, value) in self
+            .headers
+            .iter()
+            .filter_map(|rule| rule.headers_for(path))
+            .flatten()
+        {
+            let value = match headers
+                .get(name)
+                .and_then(|existing| existing.to_str().ok())
+            {
+                Some(existing) => {
+                    let joined = format!("{existing}, {}", value.to_str().unwrap_or_default());
+                    HeaderValue::from_str(&joined).unwrap_or_else(|_| value.clone())
+                }
+                None => value.clone(),
+            };
+
+            headers.insert(name, value);
+        }
+
+        headers
+    }
+}
+
+/// The current rules, shared between requests and reloaded when the files change
+#[derive(Debug, Clone)]
+pub struct SharedRules {
+    root_dir: PathBuf,
+    loaded: Arc<RwLock<Arc<Rules>>>,
+}
+
+impl SharedRules {
+    pub fn load(root_dir: &Path) -> Self {
+        let loaded = Arc::new(Rules::load(root_dir));
+        log_loaded(&loaded);
+
+        Self {
+            root_dir: root_dir.to_path_buf(),
+            loaded: Arc::new(RwLock::new(loaded)),
+        }
+    }
+
+    pub fn current(&self) -> Arc<Rules> {
+        self.loaded
+            .read()
+            .map(|loaded| loaded.clone())
+            .unwrap_or_default()
+    }
+
+    /// Reloads the rules if either file is among the changed paths, returns whether it did
+    pub fn reload_if_changed(&self, changed: &[PathBuf]) -> bool {
+        let is_rules_file = |path: &PathBuf| {
+            path.parent()
+                .is_some_and(|parent| same_dir(parent, &self.root_dir))
+                && path
+                    .file_name()
+                    .is_some_and(|name| name == REDIRECTS_FILE || name == HEADERS_FILE)
+        };
+
+        if !changed.iter().any(is_rules_file) {
+            return false;
+        }
+
+        let Ok(mut loaded) = self.loaded.write() else {
+            error!("Unable to reload {REDIRECTS_FILE} and {HEADERS_FILE}");
+            return false;
+        };
+
+        info!("Reloading {REDIRECTS_FILE} and {HEADERS_FILE} ...");
+
+        *loaded = Arc::new(Rules::load(&self.root_dir));
+        log_loaded(&loaded);
+
+        true
+    }
+}
+
+/// Applies `_redirects` before the request is served and `_headers` to the response
+pub async fn apply(State(shared): State<SharedRules>, mut req: Request, next: Next) -> Response {
+    let rules = shared.current();
+
+    if rules.is_empty() {
+        return next.run(req).await;
+    }
+
+    let path = req.uri().path().to_string();
+    let root = std::fs::canonicalize(&shared.root_dir).unwrap_or_else(|_| shared.root_dir.clone());
+
+    let mut response = match rules.action(&root, req.uri()) {
+        Some(Action::Redirect { status, location }) => {
+            info!("Redirecting {path} to {location} ({})", status.as_u16());
+            redirect(status, &location)
+        }
+
+        Some(Action::Rewrite {
+            status,
+            path: rewritten,
+        }) => {
+            info!("Rewriting {path} to {rewritten} ({})", status.as_u16());
+
+            match rewrite_uri(req.uri(), &rewritten) {
+                Some(uri) => *req.uri_mut() = uri,
+                None => error!("Invalid rewrite target in {REDIRECTS_FILE}: {rewritten}"),
+            }
+
+            let mut response = next.run(req).await;
+
+            if status != StatusCode::OK {
+                *response.status_mut() = status;
+            }
+
+            response
+        }
+
+        None => next.run(req).await,
+    };
+
+    for (name, value) in &rules.headers(&path) {
+        response.headers_mut().insert(name, value.clone());
+    }
+
+    response
+}
+
+fn redirect(status: StatusCode, location: &str) -> Response {
+    match HeaderValue::from_str(location) {
+        Ok(location) => (status, [(header::LOCATION, location)]).into_response(),
+        Err(_) => {
+            error!("Invalid redirect target in {REDIRECTS_FILE}: {location}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+// keeps the original query string unless the target has its own
+fn rewrite_uri(uri: &Uri, target: &str) -> Option<Uri> {
+    let path_and_query = match uri.query() {
+        Some(query) if !target.contains('?') => format!("{target}?{query}"),
+        _ => target.to_string(),
+    };
+
+    path_and_query.parse().ok()
+}
+
+// the watcher reports canonical paths, the root dir may be relative
+fn same_dir(dir: &Path, root_dir: &Path) -> bool {
+    dir == root_dir || std::fs::canonicalize(root_dir).is_ok_and(|root_dir| dir == root_dir)
+}
+
+fn log_loaded(rules: &Rules) {
+    if !rules.is_empty() {
+        info!(
+            "Loaded {} redirect rules and {} header rules",
+            rules.redirects.len(),
+            rules.headers.len()
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use axum::http::{HeaderName, HeaderValue};
+use log::warn;
+
+use super::pattern::Pattern;
+
+/// A block from `_headers`, a path followed by indented `Name: value` lines
+#[derive(Debug, Clone)]
+pub struct HeaderRule {
+    path: Pattern,
+    headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl HeaderRule {
+    pub fn headers_for(&self, path: &str) -> Option<&[(HeaderName, HeaderValue)]> {
+        self.path.matches(path).map(|_| self.headers.as_slice())
+    }
+}
+
+/// Parses a `_headers` file, invalid lines are skipped with a warning like static hosts do
+pub fn parse(contents: &str) -> Vec<HeaderRule> {
+    let mut rules: Vec<HeaderRule> = vec![];
+
+    // headers under a path that was skipped are skipped too
+    let mut in_rule = false;
+
+    for (index, line) in contents.lines().enumerate() {
+        let is_indented = line.starts_with([' ', '\t']);
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if !is_indented {
+            in_rule = line.starts_with('/');
+
+            if in_rule {
+                rules.push(HeaderRule {
+                    path: Pattern::parse(line),
+                    headers: vec![],
+                });
+            } else {
+                warn!(
+                    "Skipping line {} of _headers, {line} is not a path",
+                    index + 1
+                );
+            }
+
+            continue;
+        }
+
+        let Some(rule) = rules.last_mut().filter(|_| in_rule) else {
+            warn!("Skipping line {} of _headers, it has no path", index + 1);
+            continue;
+        };
+
+        match parse_header(line) {
+            Some(header) => rule.headers.push(header),
+            None => warn!(
+                "Skipping line {} of _headers, expected `Name: value`",
+                index + 1
+            ),
+        }
+    }
+
+    rules
+}
+
+fn parse_header(line: &str) -> Option<(HeaderName, HeaderValue)> {
+    let (name, value) = line.split_once(':')?;
+
+    let name = HeaderName::from_bytes(name.trim().as_bytes()).ok()?;
+    let value = HeaderValue::from_str(value.trim()).ok()?;
+
+    Some((name, value))
+}
@@ -0,0 +1,159 @@
+/// A path from a `_redirects` or `_headers` file, ex: `/blog/:year/:slug` or `/news/*`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    segments: Vec<Segment>,
+    splat: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Placeholder(String),
+}
+
+/// Values captured by placeholders, the part matched by `*` is named `splat`
+pub type Params = Vec<(String, String)>;
+
+impl Pattern {
+    pub fn parse(pattern: &str) -> Self {
+        let mut segments = segments(pattern).collect::<Vec<_>>();
+        let splat = segments.last() == Some(&"*");
+
+        if splat {
+            segments.pop();
+        }
+
+        let segments = segments
+            .into_iter()
+            .map(|segment| match segment.strip_prefix(':') {
+                Some(name) => Segment::Placeholder(name.to_string()),
+                None => Segment::Literal(segment.to_string()),
+            })
+            .collect();
+
+        Self { segments, splat }
+    }
+
+    /// Matches a url path, `/about` and `/about/` are treated the same like static hosts do
+    pub fn matches(&self, path: &str) -> Option<Params> {
+        let parts = segments(path).collect::<Vec<_>>();
+
+        if parts.len() < self.segments.len() || (!self.splat && parts.len() > self.segments.len()) {
+            return None;
+        }
+
+        let mut params = Params::new();
+
+        for (segment, part) in self.segments.iter().zip(&parts) {
+            match segment {
+                Segment::Literal(literal) if literal.eq_ignore_ascii_case(part) => {}
+                Segment::Literal(_) => return None,
+                Segment::Placeholder(name) => params.push((name.clone(), part.to_string())),
+            }
+        }
+
+        if self.splat {
+            let rest = parts[self.segments.len()..].join("/");
+            params.push(("splat".to_string(), rest));
+        }
+
+        Some(params)
+    }
+}
+
+/// Replaces `:splat` and `:name` placeholders in a redirect target with the captured values
+pub fn substitute(target: &str, params: &Params) -> String {
+    let mut params = params.iter().collect::<Vec<_>>();
+
+    // longest names first so `:id` doesn't replace the start of `:identifier`
+    params.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+
+    params
+        .into_iter()
+        .fold(target.to_string(), |target, (name, value)| {
+            target.replace(&format!(":{name}"), value)
+        })
+}
+
+fn segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> Params {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn matches_literals_ignoring_case_and_trailing_slashes() {
+        let pattern = Pattern::parse("/About");
+
+        assert_eq!(pattern.matches("/about"), Some(vec![]));
+        assert_eq!(pattern.matches("/about/"), Some(vec![]));
+        assert_eq!(pattern.matches("/about/team"), None);
+        assert_eq!(pattern.matches("/contact"), None);
+    }
+
+    #[test]
+    fn captures_placeholders() {
+        let pattern = Pattern::parse("/blog/:year/:slug");
+
+        assert_eq!(
+            pattern.matches("/blog/2024/hello"),
+            Some(params(&[("year", "2024"), ("slug", "hello")]))
+        );
+        assert_eq!(pattern.matches("/blog/2024"), None);
+    }
+
+    #[test]
+    fn captures_the_rest_of_the_path_as_splat() {
+        let pattern = Pattern::parse("/news/*");
+
+        assert_eq!(
+            pattern.matches("/news/2024/01/post"),
+            Some(params(&[("splat", "2024/01/post")]))
+        );
+        assert_eq!(pattern.matches("/news"), Some(params(&[("splat", "")])));
+        assert_eq!(pattern.matches("/other/post"), None);
+    }
+
+    #[test]
+    fn root_splat_matches_everything() {
+        let pattern = Pattern::parse("/*");
+
+        assert_eq!(pattern.matches("/"), Some(params(&[("splat", "")])));
+        assert_eq!(pattern.matches("/a/b"), Some(params(&[("splat", "a/b")])));
+    }
+
+    #[test]
+    fn substitutes_placeholders() {
+        assert_eq!(
+            substitute(
+                "/posts/:year/:slug",
+                &params(&[("year", "2024"), ("slug", "hello")])
+            ),
+            "/posts/2024/hello"
+        );
+        assert_eq!(
+            substitute("/archive/:splat", &params(&[("splat", "a/b")])),
+            "/archive/a/b"
+        );
+    }
+
+    #[test]
+    fn substitutes_longer_names_first() {
+        assert_eq!(
+            substitute(
+                "/:identifier/:id",
+                &params(&[("id", "1"), ("identifier", "user")])
+            ),
+            "/user/1"
+        );
+    }
+}
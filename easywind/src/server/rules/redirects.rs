@@ -0,0 +1,317 @@
+use axum::http::StatusCode;
+use log::warn;
+
+use super::pattern::{self, Params, Pattern};
+
+/// Conditions that depend on the visitor, these can't be tested locally
+static UNSUPPORTED_CONDITIONS: &[&str] = &["Country", "Language", "Role", "Cookie"];
+
+/// A line from `_redirects`, ex: `/blog/:slug /posts/:slug 301!`
+#[derive(Debug, Clone)]
+pub struct Redirect {
+    from: Pattern,
+    /// Query params that must be present, ex: `id=:id`
+    query: Vec<(String, String)>,
+    to: String,
+    status: StatusCode,
+    /// Applied even when a file exists at the path, set with a trailing `!`
+    force: bool,
+}
+
+/// What to do with a request that matched a redirect rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Redirect {
+        status: StatusCode,
+        location: String,
+    },
+    /// Serve another path without changing the url, with a 200 or 404 status
+    Rewrite { status: StatusCode, path: String },
+}
+
+impl Redirect {
+    /// The action for a request, `file_exists` is only checked for rules without `!` since
+    /// files shadow redirects by default
+    pub fn action(
+        &self,
+        path: &str,
+        query: Option<&str>,
+        file_exists: impl Fn() -> bool,
+    ) -> Option<Action> {
+        let mut params = self.from.matches(path)?;
+        params.extend(self.query_params(query)?);
+
+        if !self.force && file_exists() {
+            return None;
+        }
+
+        let target = pattern::substitute(&self.to, &params);
+
+        if self.status.is_redirection() {
+            // the query string is passed through unless the target sets its own, it has to go
+            // before the fragment or the browser reads it as part of the fragment
+            let (url, fragment) = match target.split_once('#') {
+                Some((url, fragment)) => (url, Some(fragment)),
+                None => (target.as_str(), None),
+            };
+
+            let mut location = match query {
+                Some(query) if !url.contains('?') => format!("{url}?{query}"),
+                _ => url.to_string(),
+            };
+
+            if let Some(fragment) = fragment {
+                location = format!("{location}#{fragment}");
+            }
+
+            return Some(Action::Redirect {
+                status: self.status,
+                location,
+            });
+        }
+
+        Some(Action::Rewrite {
+            status: self.status,
+            path: target,
+        })
+    }
+
+    fn query_params(&self, query: Option<&str>) -> Option<Params> {
+        let pairs = query
+            .unwrap_or_default()
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .collect::<Vec<_>>();
+
+        self.query
+            .iter()
+            .map(|(key, expected)| {
+                let (_, value) = pairs.iter().find(|(name, _)| name == key)?;
+
+                match expected.strip_prefix(':') {
+                    Some(name) => Some(Some((name.to_string(), value.to_string()))),
+                    None => (expected == value).then_some(None),
+                }
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(|params| params.into_iter().flatten().collect())
+    }
+}
+
+/// Parses a `_redirects` file, invalid lines are skipped with a warning like static hosts do
+pub fn parse(contents: &str) -> Vec<Redirect> {
+    contents
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let line = strip_comment(line).trim();
+
+            if line.is_empty() {
+                return None;
+            }
+
+            parse_line(line)
+                .map_err(|error| warn!("Skipping line {} of _redirects, {error}", index + 1))
+                .ok()
+        })
+        .collect()
+}
+
+// `#` only starts a comment at the start of the line or after whitespace, so fragments in
+// targets like `/new#section` are kept
+fn strip_comment(line: &str) -> &str {
+    let start = line.char_indices().find(|&(index, char)| {
+        char == '#'
+            && line[..index]
+                .chars()
+                .next_back()
+                .is_none_or(char::is_whitespace)
+    });
+
+    match start {
+        Some((index, _)) => &line[..index],
+        None => line,
+    }
+}
+
+fn parse_line(line: &str) -> Result<Redirect, String> {
+    let mut tokens = line.split_whitespace();
+    let from = tokens.next().ok_or("missing the path to redirect from")?;
+
+    if !from.starts_with('/') {
+        return Err(format!(
+            "{from} is not a path, only paths can be redirected locally"
+        ));
+    }
+
+    let mut query = vec![];
+    let mut to = None;
+
+    // conditions come between the two paths, ex: `/store id=:id /products/:id`
+    for token in tokens.by_ref() {
+        let is_target = token.starts_with('/') || token.contains("://");
+
+        match token.split_once('=') {
+            Some((key, _)) if !is_target && UNSUPPORTED_CONDITIONS.contains(&key) => {
+                return Err(format!("{key} conditions are not supported"));
+            }
+            Some((key, value)) if !is_target => query.push((key.to_string(), value.to_string())),
+            _ => {
+                to = Some(token);
+                break;
+            }
+        }
+    }
+
+    let to = to.ok_or("missing the path to redirect to")?;
+
+    let (status, force) = match tokens.next() {
+        Some(status) => {
+            let force = status.ends_with('!');
+            let status = status
+                .trim_end_matches('!')
+                .parse::<u16>()
+                .ok()
+                .and_then(|status| StatusCode::from_u16(status).ok())
+                .ok_or_else(|| format!("invalid status code {status}"))?;
+
+            (status, force)
+        }
+        None => (StatusCode::MOVED_PERMANENTLY, false),
+    };
+
+    // visitor conditions follow the status, ex: `/ /fr 302 Language=fr`
+    if let Some((key, _)) = tokens
+        .filter_map(|token| token.split_once('='))
+        .find(|(key, _)| UNSUPPORTED_CONDITIONS.contains(key))
+    {
+        return Err(format!("{key} conditions are not supported"));
+    }
+
+    let is_supported =
+        status.is_redirection() || status == StatusCode::OK || status.is_client_error();
+
+    if !is_supported {
+        return Err(format!("status {status} is not supported"));
+    }
+
+    if !status.is_redirection() && !to.starts_with('/') {
+        return Err(format!(
+            "{to} can't be proxied, only paths can be rewritten locally"
+        ));
+    }
+
+    // the browser never sends a fragment, so a rewritten request can't have one
+    if !status.is_redirection() && to.contains('#') {
+        return Err(format!("{to} has a fragment, only redirects can have one"));
+    }
+
+    Ok(Redirect {
+        from: Pattern::parse(from),
+        query,
+        to: to.to_string(),
+        status,
+        force,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(line: &str, path: &str, query: Option<&str>) -> Option<Action> {
+        parse_line(line).unwrap().action(path, query, || false)
+    }
+
+    #[test]
+    fn parses_paths_status_and_force() {
+        let redirect = parse_line("/blog/:slug /posts/:slug 302!").unwrap();
+
+        assert_eq!(redirect.from, Pattern::parse("/blog/:slug"));
+        assert_eq!(redirect.to, "/posts/:slug");
+        assert_eq!(redirect.status, StatusCode::FOUND);
+        assert!(redirect.force);
+    }
+
+    #[test]
+    fn defaults_to_a_permanent_redirect() {
+        let redirect = parse_line("/old /new").unwrap();
+
+        assert_eq!(redirect.status, StatusCode::MOVED_PERMANENTLY);
+        assert!(!redirect.force);
+    }
+
+    #[test]
+    fn parses_query_conditions() {
+        assert_eq!(
+            action("/store id=:id /products/:id 301", "/store", Some("id=42")),
+            Some(Action::Redirect {
+                status: StatusCode::MOVED_PERMANENTLY,
+                location: "/products/42?id=42".to_string(),
+            })
+        );
+
+        assert_eq!(action("/store id=:id /products/:id", "/store", None), None);
+    }
+
+    #[test]
+    fn rejects_invalid_lines() {
+        assert!(parse_line("old /new").is_err());
+        assert!(parse_line("/old").is_err());
+        assert!(parse_line("/old /new abc").is_err());
+        assert!(parse_line("/old /new 500").is_err());
+        assert!(parse_line("/api/* https://example.com/:splat 200").is_err());
+        assert!(parse_line("/ /fr 302 Language=fr").is_err());
+        assert!(parse_line("/app/* /index.html#top 200").is_err());
+    }
+
+    #[test]
+    fn rewrites_keep_the_status() {
+        assert_eq!(
+            action("/app/* /index.html 200", "/app/users/42", None),
+            Some(Action::Rewrite {
+                status: StatusCode::OK,
+                path: "/index.html".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn files_shadow_rules_without_force() {
+        let shadowed = parse_line("/about /about-us").unwrap();
+        let forced = parse_line("/about /about-us 301!").unwrap();
+
+        assert_eq!(shadowed.action("/about", None, || true), None);
+        assert!(forced.action("/about", None, || true).is_some());
+    }
+
+    #[test]
+    fn keeps_fragments_in_targets() {
+        let redirects = parse(
+            "# moved sections\n/old /new#section 301 # trailing comment\n\n  # indented comment",
+        );
+
+        assert_eq!(redirects.len(), 1);
+        assert_eq!(redirects[0].to, "/new#section");
+        assert_eq!(redirects[0].status, StatusCode::MOVED_PERMANENTLY);
+    }
+
+    #[test]
+    fn passes_the_query_before_the_fragment() {
+        assert_eq!(
+            action("/old /new#top 301", "/old", Some("a=1")),
+            Some(Action::Redirect {
+                status: StatusCode::MOVED_PERMANENTLY,
+                location: "/new?a=1#top".to_string(),
+            })
+        );
+
+        assert_eq!(
+            action("/old /new?b=2#top 301", "/old", Some("a=1")),
+            Some(Action::Redirect {
+                status: StatusCode::MOVED_PERMANENTLY,
+                location: "/new?b=2#top".to_string(),
+            })
+        );
+    }
+}
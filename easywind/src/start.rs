@@ -1,5 +1,5 @@
 use color_eyre::Help;
-use eyre::{Context, Result};
+use eyre::{eyre, Context, Result};
 use futures::{stream::FuturesUnordered, StreamExt};
 use std::path::PathBuf;
 use tokio::task::JoinHandle;
@@ -7,18 +7,51 @@ use tokio::task::JoinHandle;
 #[derive(Debug, Clone)]
 pub struct StartArgs {
     pub root_dir: PathBuf,
+    pub host: String,
     pub port: u16,
+    pub strict_port: bool,
     pub open: bool,
     pub input: Option<PathBuf>,
     pub output: Option<PathBuf>,
+    pub proxy: Option<String>,
+    pub hidden: Vec<String>,
+    pub symlinks: crate::server::access::SymlinkPolicy,
+    pub cache: crate::server::cache::CacheMode,
+    pub compress: bool,
+    pub spa: Option<PathBuf>,
+    pub email: bool,
+    pub tailwind_version: Option<String>,
+    pub tailwind_download: crate::tailwind::installer::DownloadOptions,
+    pub tailwind_runner: crate::tailwind::runner::RunnerKind,
 }
 
 impl From<StartArgs> for crate::server::ServerArgs {
     fn from(args: StartArgs) -> Self {
+        let output = args
+            .output
+            .unwrap_or_else(|| args.root_dir.join(crate::consts::DEFAULT_OUTPUT));
+
+        let input = args
+            .input
+            .unwrap_or_else(|| args.root_dir.join(crate::consts::DEFAULT_INPUT));
+
         Self {
+            stylesheet: Some(crate::server::reload::url_path(&output, &args.root_dir)),
+            tailwind_input: Some(input),
             root_dir: args.root_dir,
+            host: args.host,
             port: args.port,
+            strict_port: args.strict_port,
             open: args.open,
+            proxy: args.proxy,
+            reloader: Default::default(),
+            hidden: args.hidden,
+            symlinks: args.symlinks,
+            cache: args.cache,
+            compress: args.compress,
+            livereload: true,
+            spa: args.spa,
+            email: args.email,
         }
     }
 }
@@ -75,15 +108,28 @@ impl TryFrom<StartArgs> for crate::tailwind::TailwindArgs {
             input,
             output,
             watch: true,
+            minify: false,
+            version: args.tailwind_version,
+            download: args.tailwind_download,
+            runner: args.tailwind_runner,
+            reloader: None,
         })
     }
 }
 
 pub async fn start(args: StartArgs) -> Result<()> {
-    let server_args: crate::server::ServerArgs = args.clone().into();
+    let reloader = crate::server::livereload::Reloader::new();
+
+    let server_args = crate::server::ServerArgs {
+        reloader: reloader.clone(),
+        ..args.clone().into()
+    };
     let server_task = tokio::task::spawn(async move { crate::server::start(server_args).await });
 
-    let tailwind_args: crate::tailwind::TailwindArgs = args.try_into()?;
+    let tailwind_args = crate::tailwind::TailwindArgs {
+        reloader: Some(reloader),
+        ..args.try_into()?
+    };
     let tailwind_task = tokio::task::spawn_blocking(|| crate::tailwind::start(tailwind_args));
 
     let tasks = vec![tailwind_task, server_task];
@@ -92,10 +138,11 @@ pub async fn start(args: StartArgs) -> Result<()> {
         .into_iter()
         .collect::<FuturesUnordered<JoinHandle<_>>>();
 
-    // return on first errror
-    if let Some(Ok(Err(err))) = futures.next().await {
-        return Err(err);
-    };
-
-    Ok(())
+    // the server and the tailwind supervisor only return when they fail or give up
+    match futures.next().await {
+        Some(Ok(result)) => result,
+        Some(Err(error)) if error.is_panic() => Err(eyre!("easywind crashed: {error}")),
+        Some(Err(error)) => Err(error).wrap_err("easywind task was cancelled"),
+        None => Ok(()),
+    }
 }
@@ -1,10 +1,23 @@
 pub mod cli;
 pub mod installer;
+pub mod output;
+pub mod runner;
+pub mod supervisor;
+pub mod version;
 
 use std::path::PathBuf;
 
 use color_eyre::Help;
 use eyre::{eyre, Result};
+use log::info;
+
+use crate::server::livereload::Reloader;
+
+use self::{
+    installer::DownloadOptions,
+    runner::{RunnerKind, TailwindRunner},
+    version::TailwindVersion,
+};
 
 #[derive(Debug, Clone)]
 pub struct TailwindArgs {
@@ -12,6 +25,14 @@ pub struct TailwindArgs {
     pub input: PathBuf,
     pub output: PathBuf,
     pub watch: bool,
+    /// Minify the output css, for production builds
+    pub minify: bool,
+    /// Exact version pinned in the config, ex: 4.1.13
+    pub version: Option<String>,
+    pub download: DownloadOptions,
+    pub runner: RunnerKind,
+    /// Build results are sent to the browser while watching, when running with the server
+    pub reloader: Option<Reloader>,
 }
 
 pub fn start(args: TailwindArgs) -> Result<()> {
@@ -29,13 +50,14 @@ pub fn start(args: TailwindArgs) -> Result<()> {
         .suggestion("Try setting the location of your input file with `--input` flag");
     }
 
-    // check or install or install tailwind on each start
-    crate::tailwind::installer::check_or_install()?;
+    let version = TailwindVersion::resolve(args.version.as_deref(), &args.root_dir, &args.input)?;
+    let runner = TailwindRunner::resolve(args.runner, &version, &args.root_dir, &args.download)?;
+    info!("Using {runner}");
 
     if args.watch {
-        self::cli::watch(args)?;
+        self::supervisor::watch(args, runner)?;
     } else {
-        self::cli::build(args)?;
+        self::cli::build(args, &runner)?;
     };
 
     Ok(())
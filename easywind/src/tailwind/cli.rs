@@ -3,51 +3,42 @@ use std::{
     path::PathBuf,
 };
 
+use color_eyre::Help;
 use eyre::{eyre, Context, Result};
 
-use crate::{consts::TAILWIND_CLI_PATH, validate};
+use super::{runner::TailwindRunner, version::TailwindMajor, TailwindArgs};
 
-use super::{installer, TailwindArgs};
+pub fn build(args: TailwindArgs, runner: &TailwindRunner) -> Result<()> {
+    let tailwind_args = base_args(&args, runner.version.major)?;
 
-pub fn watch(args: TailwindArgs) -> Result<()> {
-    let mut tailwind_args = base_args(&args)?;
-    tailwind_args.push("--watch");
-
-    if tailwind(&tailwind_args, &args.root_dir).is_err() {
-        installer::check_npx_tailwind_works()?;
-        tailwind(&tailwind_args, &args.root_dir).wrap_err("failed to run tailwind")?
-    }
-
-    Ok(())
+    run(&tailwind_args, &args, runner)
 }
 
-pub fn build(args: TailwindArgs) -> Result<()> {
-    let tailwind_args = base_args(&args)?;
+fn run(tailwind_args: &[&str], args: &TailwindArgs, runner: &TailwindRunner) -> Result<()> {
+    if let Err(error) = tailwind(tailwind_args, &args.root_dir, runner) {
+        // npx didn't work, lets try the standalone cli
+        let Some(fallback) = runner.fallback(&args.download) else {
+            return Err(error).wrap_err("failed to run tailwind");
+        };
 
-    if tailwind(&tailwind_args, &args.root_dir).is_err() {
-        installer::check_npx_tailwind_works()?;
-        tailwind(&tailwind_args, &args.root_dir).wrap_err("failed to run tailwind")?
+        tailwind(tailwind_args, &args.root_dir, &fallback?).wrap_err("failed to run tailwind")?
     }
 
     Ok(())
 }
 
-pub fn npx_works() -> Result<()> {
-    duct::cmd("npx", ["tailwindcss", "--help"]).run()?;
-    Ok(())
-}
-
-fn base_args(args: &TailwindArgs) -> Result<Vec<&str>> {
-    let config_file = args.root_dir.join("tailwind.config.js");
-
-    if !config_file.exists() {
+pub(super) fn base_args(args: &TailwindArgs, major: TailwindMajor) -> Result<Vec<&str>> {
+    // v4 is configured in the input css, only v3 needs a config file
+    if major == TailwindMajor::V3 && TailwindMajor::config_file(&args.root_dir).is_none() {
         return Err(eyre!(
             "tailwind.config.js does not exist in {}",
             args.root_dir.to_string_lossy()
-        ));
+        ))
+        .suggestion("Tailwind v3 projects need a tailwind.config.js file")
+        .suggestion("To use tailwind v4 add `@import \"tailwindcss\";` to your input css file");
     }
 
-    let base_args = vec![
+    let mut base_args = vec![
         "--input",
         args.input
             .to_str()
@@ -58,17 +49,20 @@ fn base_args(args: &TailwindArgs) -> Result<Vec<&str>> {
             .ok_or_else(|| eyre!("output path is not valid utf-8"))?,
     ];
 
+    if args.minify {
+        base_args.push("--minify");
+    }
+
     Ok(base_args)
 }
 
-pub fn tailwind(args: &[&str], root_dir: &PathBuf) -> Result<(), std::io::Error> {
-    let tailwind = if validate::check_node_deps().is_ok() {
-        duct::cmd("npx", ["tailwindcss"].iter().chain(args))
-    } else {
-        duct::cmd(TAILWIND_CLI_PATH.as_os_str(), args)
-    };
-
-    let reader = tailwind
+pub fn tailwind(
+    args: &[&str],
+    root_dir: &PathBuf,
+    runner: &TailwindRunner,
+) -> Result<(), std::io::Error> {
+    let reader = runner
+        .command(args)
         .stderr_to_stdout()
         .stdout_capture()
         .dir(root_dir)
@@ -1,115 +1,226 @@
+mod download;
+mod platform;
+
+use std::{
+    fs::{File, TryLockError},
+    path::Path,
+};
+
 use crate::{
-    consts::{LATEST_TAILWIND_VERSION, TAILWIND_BIN_DIR, TAILWIND_CLI_PATH},
+    consts::{tailwind_bin_dir, tailwind_cli_path, TAILWIND_BIN_ROOT, TAILWIND_DOWNLOAD_URL},
     validate,
 };
 
+use super::version::TailwindVersion;
+
+pub use platform::Platform;
+
+use color_eyre::Help;
 use eyre::{eyre, Context, Result};
 use log::{info, warn};
 
-pub fn check_or_install() -> Result<()> {
-    // if node is available return, we can just use npx
-    if validate::check_node_deps().is_ok() {
+/// Where the standalone cli is downloaded from
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// Base url of the releases, `v<version>/<asset>` is appended to it
+    pub base_url: String,
+    /// Proxy to download through, the `HTTPS_PROXY` and `ALL_PROXY` env vars are used if not set
+    pub proxy: Option<String>,
+    /// Install a release that has no published checksums, with a warning, off by default
+    pub skip_checksum: bool,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            base_url: TAILWIND_DOWNLOAD_URL.to_string(),
+            proxy: None,
+            skip_checksum: false,
+        }
+    }
+}
+
+pub fn check_or_install(version: &TailwindVersion, download: &DownloadOptions) -> Result<()> {
+    // if node is available return, we can just use npx, unless a specific version was pinned
+    if !version.pinned && validate::check_node_deps().is_ok() {
         info!("Node is installed, will use tailwind from npm");
         return Ok(());
     };
 
     // if tailwind standalone cli is avilable use it
-    if TAILWIND_CLI_PATH.exists() {
-        info!("Tailwind standalone CLI is available, will use it");
+    if version.is_installed() {
+        info!("Tailwind standalone CLI ({version}) is available, will use it");
         return Ok(());
     }
 
     // Node and tailwind cli not present, must download
-    warn!("Node is not installed, installing standalone cli");
-
-    clean_and_download_cli()?;
-    info!("Successfully downloaded tailwind cli");
+    if version.pinned {
+        warn!("Tailwind {version} is pinned but not installed, installing standalone cli");
+    } else {
+        warn!("Node is not installed, installing standalone cli");
+    }
 
-    // make cli executable on unix, funciton is no-op on windows
-    make_tailwind_cli_executable()?;
+    install_if_missing(&version.version, download)?;
 
     Ok(())
 }
 
-pub fn check_npx_tailwind_works() -> Result<()> {
-    // npx didn't work, lets try downloading it
-    if validate::check_node_deps().is_ok()
-        && crate::tailwind::cli::npx_works().is_err()
-        && !TAILWIND_CLI_PATH.exists()
-    {
-        clean_and_download_cli()?;
+/// All versions of the standalone cli in the cache, oldest first
+pub fn installed_versions() -> Result<Vec<TailwindVersion>> {
+    if !TAILWIND_BIN_ROOT.exists() {
+        return Ok(vec![]);
     }
 
-    Ok(())
+    let mut versions = std::fs::read_dir(TAILWIND_BIN_ROOT.as_path())?
+        .filter_map(Result::ok)
+        .filter_map(|entry| TailwindVersion::parse(&entry.file_name().to_string_lossy()).ok())
+        .filter(TailwindVersion::is_installed)
+        .collect::<Vec<_>>();
+
+    versions.sort_by_cached_key(|version| {
+        version
+            .version
+            .split(['.', '-'])
+            .map(|part| part.parse::<u32>().unwrap_or_default())
+            .collect::<Vec<_>>()
+    });
+
+    Ok(versions)
 }
 
-pub fn clean_and_download_cli() -> Result<()> {
-    // clean old version of tailwind cli if it exists
-    if TAILWIND_BIN_DIR.exists() {
-        info!("Cleaning up old version of tailwind cli");
+/// Removes a single version from the cache, other versions are left alone
+pub fn remove(version: &TailwindVersion) -> Result<()> {
+    let _lock = lock(&version.version)?;
+    let bin_dir = tailwind_bin_dir(&version.version);
 
-        if let Err(error) = std::fs::remove_dir_all(TAILWIND_BIN_DIR.as_path()) {
-            warn!("Unable to remove old version of tailwind cli: {error:?}");
-        }
+    if !bin_dir.exists() {
+        return Err(eyre!("Tailwind {version} is not installed"))
+            .suggestion("Run `easywind tailwind list` to see the installed versions");
     }
 
-    // download latest tailwind css cli
+    std::fs::remove_dir_all(&bin_dir)
+        .wrap_err_with(|| format!("Unable to remove {}", bin_dir.display()))?;
+
+    info!("Removed tailwind {version}");
+
+    Ok(())
+}
+
+/// Downloads a version of the standalone cli, an already installed copy is only replaced once
+/// the new download has been verified
+pub fn install_cli(version: &str, download: &DownloadOptions) -> Result<()> {
+    let _lock = lock(version)?;
+
     info!(
-        "Downloading latest tailwind css cli (v{}) from github",
-        LATEST_TAILWIND_VERSION
+        "Downloading tailwind css cli (v{version}) from {}",
+        download.base_url
     );
 
-    download_tailwind_cli()
+    download::download_tailwind_cli(version, asset_name(version)?, download)?;
+    info!("Successfully installed tailwind cli (v{version})");
+
+    Ok(())
 }
 
-fn download_tailwind_cli() -> Result<()> {
-    let tailwind_version = LATEST_TAILWIND_VERSION;
-    let os = std::env::consts::OS;
-    let arch = get_arch()?;
+fn install_if_missing(version: &str, download: &DownloadOptions) -> Result<()> {
+    let _lock = lock(version)?;
+
+    // another easywind process might have installed it while we were waiting for the lock
+    if tailwind_cli_path(version).exists() {
+        return Ok(());
+    }
 
-    let download_link = format!(
-        "https://github.com/tailwindlabs/tailwindcss/releases/download/v{tailwind_version}/tailwindcss-{os}-{arch}",
+    info!(
+        "Downloading tailwind css cli (v{version}) from {}",
+        download.base_url
     );
 
-    let mut response = ureq::get(&download_link).call()?;
-    let mut reader = response.body_mut().as_reader();
+    download::download_tailwind_cli(version, asset_name(version)?, download)?;
+    info!("Successfully installed tailwind cli (v{version})");
+
+    Ok(())
+}
+
+/// Installs a standalone cli that was already downloaded, `from` is a path or a `file://` url.
+/// If no version is given it is read from the cli's `--help` output
+pub fn import_cli(from: &str, version: Option<&str>) -> Result<TailwindVersion> {
+    let path = Path::new(from.strip_prefix("file://").unwrap_or(from));
 
-    std::fs::create_dir_all(TAILWIND_BIN_DIR.as_path())?;
+    if !path.is_file() {
+        return Err(eyre!("Tailwind cli not found at {}", path.display()));
+    }
 
-    let mut tailwind_cli_file = std::fs::OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(TAILWIND_CLI_PATH.as_path())
-        .wrap_err("Unable to create tailwind bin")?;
+    std::fs::create_dir_all(TAILWIND_BIN_ROOT.as_path())?;
 
-    std::io::copy(&mut reader, &mut tailwind_cli_file)
-        .wrap_err("Unable to save tailwind cli file")?;
+    // stage the file next to the cache so it can be moved into place atomically
+    let staged = tempfile::Builder::new()
+        .prefix(".tailwindcss-import-")
+        .tempfile_in(TAILWIND_BIN_ROOT.as_path())?
+        .into_temp_path();
 
-    Ok(())
+    std::fs::copy(path, &staged)
+        .wrap_err_with(|| format!("Unable to copy tailwind cli from {}", path.display()))?;
+
+    make_tailwind_cli_executable(&staged)?;
+    let help = download::smoke_test(&staged)?;
+
+    let version = match version {
+        Some(version) => TailwindVersion::parse(version)?,
+        None => TailwindVersion::from_help_output(&help)
+            .ok_or_else(|| eyre!("Unable to detect the version of {}", path.display()))
+            .suggestion(
+                "Pass the version explicitly, ex: `easywind tailwind install 4.1.13 --from <FILE>`",
+            )?,
+    };
+
+    let _lock = lock(&version.version)?;
+    std::fs::create_dir_all(tailwind_bin_dir(&version.version))?;
+
+    staged
+        .persist(version.cli_path())
+        .wrap_err("Unable to move tailwind cli into place")?;
+
+    info!("Installed tailwind cli ({version}) from {}", path.display());
+
+    Ok(version)
 }
 
-fn get_arch() -> Result<&'static str> {
-    let arch = std::env::consts::ARCH;
-    match arch {
-        "x86_64" => Ok("x64"),
-        "aarch64" => Ok("arm64"),
-        "arm" => Ok("armv7"),
-        _ => Err(eyre!("Unsupported architecture: {}", arch)),
+/// Only one easywind process at a time can install a version, the lock is released when the
+/// returned file is dropped
+fn lock(version: &str) -> Result<File> {
+    std::fs::create_dir_all(TAILWIND_BIN_ROOT.as_path())?;
+
+    let lock_path = TAILWIND_BIN_ROOT.join(format!("{version}.lock"));
+    let lock_file = File::create(&lock_path)
+        .wrap_err_with(|| format!("Unable to create lock file: {}", lock_path.display()))?;
+
+    match lock_file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            info!("Waiting for another easywind process to finish installing tailwind v{version}");
+            lock_file.lock()?;
+        }
+        Err(TryLockError::Error(error)) => {
+            return Err(error).wrap_err("Unable to lock tailwind install directory")
+        }
     }
+
+    Ok(lock_file)
+}
+
+/// Release asset of the standalone cli for this platform, ex: `tailwindcss-linux-x64-musl`
+fn asset_name(version: &str) -> Result<&'static str> {
+    Platform::current()?.asset_name(&TailwindVersion::parse(version)?)
 }
 
 #[cfg(unix)]
-fn make_tailwind_cli_executable() -> Result<()> {
+fn make_tailwind_cli_executable(cli_path: &Path) -> Result<()> {
     use std::os::unix::prelude::PermissionsExt;
 
-    use color_eyre::Section;
-
-    info!("Making tailwind cli executable");
-    let mut perms = TAILWIND_CLI_PATH.metadata()?.permissions();
+    let mut perms = cli_path.metadata()?.permissions();
     perms.set_mode(0o755);
 
-    std::fs::set_permissions(TAILWIND_CLI_PATH.as_path(), perms)
+    std::fs::set_permissions(cli_path, perms)
         .wrap_err("Unable to make Tailwind CLI executable")
         .suggestion("Please install node from nodejs and try again")
         .suggestion("Go to: https://nodejs.org/en/download")?;
@@ -118,6 +229,6 @@ fn make_tailwind_cli_executable() -> Result<()> {
 }
 
 #[cfg(not(unix))]
-fn make_tailwind_cli_executable() -> Result<()> {
+fn make_tailwind_cli_executable(_cli_path: &Path) -> Result<()> {
     Ok(())
 }
@@ -0,0 +1,214 @@
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    path::Path,
+    time::Duration,
+};
+
+use color_eyre::Help;
+use eyre::{eyre, Context, Result};
+use indicatif::{ProgressBar, ProgressStyle};
+use log::{info, warn};
+use sha2::{Digest, Sha256};
+use tempfile::NamedTempFile;
+
+use crate::consts::{tailwind_bin_dir, tailwind_cli_path};
+
+use super::{make_tailwind_cli_executable, DownloadOptions};
+
+static ATTEMPTS: u32 = 3;
+static RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Downloads the cli into a temp file next to its final location, verifies it and then
+/// atomically moves it into place, a failed download never replaces a working install
+pub(super) fn download_tailwind_cli(
+    version: &str,
+    asset: &str,
+    options: &DownloadOptions,
+) -> Result<()> {
+    let base_url = options.base_url.trim_end_matches('/');
+
+    let agent = agent(options)?;
+
+    let bin_dir = tailwind_bin_dir(version);
+    std::fs::create_dir_all(&bin_dir)?;
+
+    let checksums_url = format!("{base_url}/v{version}/sha256sums.txt");
+    let expected_checksum = match with_retries(|| release_checksums(&agent, &checksums_url))? {
+        Some(checksums) => Some(checksums.get(asset).cloned().ok_or_else(|| {
+            eyre!("No checksum found for {asset} in tailwind v{version} release")
+        })?),
+        None if options.skip_checksum => None,
+        None => {
+            return Err(eyre!("No checksums published at {checksums_url}"))
+                .note("The downloaded tailwind cli can't be verified without them")
+                .suggestion(
+                    "Install a cli you downloaded and vetted yourself with `easywind tailwind install --from <PATH>`",
+                )
+                .suggestion("Set `tailwind.skip_checksum = true` to install it unverified");
+        }
+    };
+
+    let download_link = format!("{base_url}/v{version}/{asset}");
+    let (temp_file, checksum) = with_retries(|| download(&agent, &download_link, &bin_dir))?;
+
+    match expected_checksum {
+        Some(expected) if expected != checksum => {
+            return Err(eyre!(
+                "Checksum mismatch for downloaded tailwind cli ({asset})"
+            ))
+            .note(format!("downloaded file has sha256 {checksum}"))
+            .suggestion("The download might have been interrupted, please try again");
+        }
+        Some(_) => info!("Verified tailwind cli checksum"),
+        None => warn!(
+            "Installing an UNVERIFIED tailwind cli, {checksums_url} doesn't exist and checksums are skipped, the download has sha256 {checksum}"
+        ),
+    }
+
+    // close the file before running it, executing a file open for writing fails on linux
+    let temp_path = temp_file.into_temp_path();
+
+    make_tailwind_cli_executable(&temp_path)?;
+    smoke_test(&temp_path)?;
+
+    temp_path
+        .persist(tailwind_cli_path(version))
+        .wrap_err("Unable to move tailwind cli into place")?;
+
+    Ok(())
+}
+
+fn agent(options: &DownloadOptions) -> Result<ureq::Agent> {
+    let mut config = ureq::Agent::config_builder();
+
+    if let Some(proxy) = &options.proxy {
+        let proxy =
+            ureq::Proxy::new(proxy).wrap_err_with(|| format!("Invalid download proxy: {proxy}"))?;
+
+        config = config.proxy(Some(proxy));
+    }
+
+    Ok(config.build().into())
+}
+
+/// Streams the download into a temp file, returns the file and its sha256 checksum
+fn download(agent: &ureq::Agent, url: &str, dir: &Path) -> Result<(NamedTempFile, String)> {
+    let mut response = agent
+        .get(url)
+        .call()
+        .wrap_err_with(|| format!("Unable to download tailwind cli from {url}"))?;
+
+    let length = response.body().content_length();
+
+    let mut temp_file = tempfile::Builder::new()
+        .prefix(".tailwindcss-download-")
+        .tempfile_in(dir)
+        .wrap_err("Unable to create temp file for tailwind cli download")?;
+
+    let progress = match length {
+        Some(length) => ProgressBar::new(length).with_style(
+            ProgressStyle::with_template("{bar:40} {bytes}/{total_bytes} ({eta})")
+                .expect("valid progress template"),
+        ),
+        None => ProgressBar::new_spinner(),
+    };
+
+    let mut reader = response.body_mut().with_config().limit(u64::MAX).reader();
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+
+    loop {
+        let read = reader
+            .read(&mut buffer)
+            .wrap_err("Unable to download tailwind cli")?;
+
+        if read == 0 {
+            break;
+        }
+
+        hasher.update(&buffer[..read]);
+        temp_file
+            .write_all(&buffer[..read])
+            .wrap_err("Unable to save tailwind cli file")?;
+
+        progress.inc(read as u64);
+    }
+
+    progress.finish_and_clear();
+    temp_file.as_file().sync_all()?;
+
+    Ok((temp_file, format!("{:x}", hasher.finalize())))
+}
+
+/// Checksums published with the release, keyed by asset name, `None` if the release has none
+fn release_checksums(agent: &ureq::Agent, url: &str) -> Result<Option<HashMap<String, String>>> {
+    let body = match agent.get(url).call() {
+        Ok(mut response) => response
+            .body_mut()
+            .read_to_string()
+            .wrap_err("Unable to read tailwind release checksums")?,
+
+        Err(ureq::Error::StatusCode(404)) => return Ok(None),
+
+        Err(error) => {
+            return Err(error)
+                .wrap_err_with(|| format!("Unable to download tailwind checksums from {url}"))
+        }
+    };
+
+    let checksums = body
+        .lines()
+        .filter_map(|line| {
+            let (checksum, file) = line.trim().split_once(char::is_whitespace)?;
+            let file = file.trim().trim_start_matches('*').trim_start_matches("./");
+
+            Some((file.to_string(), checksum.to_lowercase()))
+        })
+        .collect();
+
+    Ok(Some(checksums))
+}
+
+// retry network errors and server errors with an exponential backoff, client errors like a
+// 404 for a version that doesn't exist won't go away by trying again
+fn with_retries<T>(mut attempt: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut delay = RETRY_DELAY;
+
+    for remaining in (0..ATTEMPTS).rev() {
+        match attempt() {
+            Ok(value) => return Ok(value),
+
+            Err(error) if remaining > 0 && is_retryable(&error) => {
+                warn!("{error}, retrying in {}s", delay.as_secs());
+                std::thread::sleep(delay);
+                delay *= 2;
+            }
+
+            Err(error) => return Err(error),
+        }
+    }
+
+    unreachable!("last attempt always returns")
+}
+
+fn is_retryable(error: &eyre::Report) -> bool {
+    match error.downcast_ref::<ureq::Error>() {
+        Some(ureq::Error::StatusCode(status)) => *status >= 500,
+        Some(_) => true,
+        None => error.downcast_ref::<std::io::Error>().is_some(),
+    }
+}
+
+/// Makes sure the cli is actually a working binary and not an html error page, returns the
+/// `--help` output
+pub(super) fn smoke_test(cli_path: &Path) -> Result<String> {
+    let output = duct::cmd(cli_path, ["--help"])
+        .stderr_to_stdout()
+        .stdout_capture()
+        .run()
+        .wrap_err("Tailwind cli does not run")
+        .suggestion("Your platform might not be supported by the tailwind standalone cli")?;
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
@@ -0,0 +1,173 @@
+use std::fmt::Display;
+
+use color_eyre::Help;
+use eyre::{eyre, Result};
+
+use crate::tailwind::version::{TailwindMajor, TailwindVersion};
+
+use self::{Arch::*, Libc::*, Os::*};
+use TailwindMajor::{V3, V4};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Os {
+    Linux,
+    Macos,
+    Windows,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arch {
+    X64,
+    Arm64,
+    Armv7,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Libc {
+    Gnu,
+    Musl,
+    /// macOS and windows binaries don't depend on a specific libc
+    System,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Platform {
+    pub os: Os,
+    pub arch: Arch,
+    pub libc: Libc,
+}
+
+struct Asset {
+    major: TailwindMajor,
+    platform: Platform,
+    name: &'static str,
+}
+
+const fn asset(major: TailwindMajor, os: Os, arch: Arch, libc: Libc, name: &'static str) -> Asset {
+    Asset {
+        major,
+        platform: Platform { os, arch, libc },
+        name,
+    }
+}
+
+/// Standalone cli release assets, v4 added musl builds and dropped armv7 and windows arm64
+static ASSETS: &[Asset] = &[
+    asset(V3, Linux, X64, Gnu, "tailwindcss-linux-x64"),
+    asset(V3, Linux, Arm64, Gnu, "tailwindcss-linux-arm64"),
+    asset(V3, Linux, Armv7, Gnu, "tailwindcss-linux-armv7"),
+    asset(V3, Macos, X64, System, "tailwindcss-macos-x64"),
+    asset(V3, Macos, Arm64, System, "tailwindcss-macos-arm64"),
+    asset(V3, Windows, X64, System, "tailwindcss-windows-x64.exe"),
+    asset(V3, Windows, Arm64, System, "tailwindcss-windows-arm64.exe"),
+    asset(V4, Linux, X64, Gnu, "tailwindcss-linux-x64"),
+    asset(V4, Linux, Arm64, Gnu, "tailwindcss-linux-arm64"),
+    asset(V4, Linux, X64, Musl, "tailwindcss-linux-x64-musl"),
+    asset(V4, Linux, Arm64, Musl, "tailwindcss-linux-arm64-musl"),
+    asset(V4, Macos, X64, System, "tailwindcss-macos-x64"),
+    asset(V4, Macos, Arm64, System, "tailwindcss-macos-arm64"),
+    asset(V4, Windows, X64, System, "tailwindcss-windows-x64.exe"),
+];
+
+impl Platform {
+    pub fn current() -> Result<Self> {
+        let os = match std::env::consts::OS {
+            "linux" => Linux,
+            "macos" => Macos,
+            "windows" => Windows,
+            os => {
+                return Err(eyre!("Unsupported operating system: {os}"))
+                    .with_suggestion(supported_targets)
+            }
+        };
+
+        let arch = match std::env::consts::ARCH {
+            "x86_64" => X64,
+            "aarch64" => Arm64,
+            "arm" => Armv7,
+            arch => {
+                return Err(eyre!("Unsupported architecture: {arch}"))
+                    .with_suggestion(supported_targets)
+            }
+        };
+
+        let libc = match os {
+            Linux if is_musl() => Musl,
+            Linux => Gnu,
+            _ => System,
+        };
+
+        Ok(Self { os, arch, libc })
+    }
+
+    /// Name of the release asset to download for this platform
+    pub fn asset_name(&self, version: &TailwindVersion) -> Result<&'static str> {
+        ASSETS
+            .iter()
+            .find(|asset| asset.major == version.major && asset.platform == *self)
+            .map(|asset| asset.name)
+            .ok_or_else(|| eyre!("Tailwind {version} has no standalone cli for {self}"))
+            .with_note(|| {
+                let targets = ASSETS
+                    .iter()
+                    .filter(|asset| asset.major == version.major)
+                    .map(|asset| asset.platform.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                format!("Tailwind {} supports: {targets}", version.major)
+            })
+            .suggestion("Install node from https://nodejs.org/en/download to use tailwind from npm")
+    }
+}
+
+impl Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let os = match self.os {
+            Linux => "linux",
+            Macos => "macos",
+            Windows => "windows",
+        };
+
+        let arch = match self.arch {
+            X64 => "x64",
+            Arm64 => "arm64",
+            Armv7 => "armv7",
+        };
+
+        match self.libc {
+            Gnu => write!(f, "{os}-{arch}-gnu"),
+            Musl => write!(f, "{os}-{arch}-musl"),
+            System => write!(f, "{os}-{arch}"),
+        }
+    }
+}
+
+fn supported_targets() -> String {
+    let mut targets = ASSETS
+        .iter()
+        .map(|asset| asset.platform.to_string())
+        .collect::<Vec<_>>();
+
+    targets.sort();
+    targets.dedup();
+
+    format!("Supported platforms are: {}", targets.join(", "))
+}
+
+// alpine and other musl based distros ship the musl dynamic loader instead of glibc's
+fn is_musl() -> bool {
+    if cfg!(target_env = "musl") {
+        return true;
+    }
+
+    let has_musl_loader = std::fs::read_dir("/lib")
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .any(|entry| entry.file_name().to_string_lossy().starts_with("ld-musl-"))
+        })
+        .unwrap_or(false);
+
+    has_musl_loader || std::path::Path::new("/etc/alpine-release").exists()
+}
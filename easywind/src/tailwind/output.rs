@@ -0,0 +1,98 @@
+use serde::Serialize;
+
+/// Outcome of a tailwind build, read from the cli output while watching
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildResult {
+    Success,
+    Failed(BuildError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BuildError {
+    pub message: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+/// Parses a line of tailwind output, `None` for lines that aren't the end of a build.
+///
+/// Both v3 and v4 print `Done in 12ms` after a build, errors are printed as
+/// `Error: ...` or `CssSyntaxError: src/app.css:3:5: ...`
+pub fn parse_line(line: &str) -> Option<BuildResult> {
+    let line = line.trim();
+
+    if line.starts_with("Done in") {
+        return Some(BuildResult::Success);
+    }
+
+    let mut message = strip_error_kind(line)?;
+
+    // errors can be wrapped, ex: `Error: CssSyntaxError: ...`
+    while let Some(inner) = strip_error_kind(message) {
+        message = inner;
+    }
+
+    Some(BuildResult::Failed(BuildError::new(message)))
+}
+
+fn strip_error_kind(line: &str) -> Option<&str> {
+    let (kind, message) = line.split_once(": ")?;
+
+    (kind.ends_with("Error") && !kind.contains(char::is_whitespace)).then_some(message)
+}
+
+impl BuildError {
+    fn new(message: &str) -> Self {
+        let location = message.split_whitespace().find_map(location);
+
+        match location {
+            Some((token, file, line, column)) => {
+                // postcss puts the location first, ex: `src/app.css:3:5: Unknown word`
+                let message = message
+                    .strip_prefix(token)
+                    .map(|rest| rest.trim_start_matches(':').trim())
+                    .unwrap_or(message);
+
+                Self {
+                    message: message.to_string(),
+                    file: Some(file),
+                    line: Some(line),
+                    column,
+                }
+            }
+
+            None => Self {
+                message: message.to_string(),
+                file: None,
+                line: None,
+                column: None,
+            },
+        }
+    }
+}
+
+// a `file.css:3:5` or `file.css:3` token, the file needs an extension so that a message like
+// `expected 1:2` isn't mistaken for a location
+fn location(token: &str) -> Option<(&str, String, u32, Option<u32>)> {
+    let trimmed = token
+        .trim_end_matches([':', ',', ')'])
+        .trim_start_matches('(');
+
+    let (rest, last) = trimmed.rsplit_once(':')?;
+    let last = last.parse::<u32>().ok()?;
+
+    let (file, line, column) = match rest.rsplit_once(':') {
+        Some((file, line)) if line.parse::<u32>().is_ok() => {
+            (file, line.parse::<u32>().ok()?, Some(last))
+        }
+        _ => (rest, last, None),
+    };
+
+    let has_extension = std::path::Path::new(file).extension().is_some();
+    if file.is_empty() || !has_extension {
+        return None;
+    }
+
+    Some((token, file.to_string(), line, column))
+}
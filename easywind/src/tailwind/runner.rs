@@ -0,0 +1,241 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use color_eyre::Help;
+use eyre::{eyre, Result};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+
+use crate::validate;
+
+use super::{
+    installer::{self, DownloadOptions},
+    version::TailwindVersion,
+};
+
+/// Which tailwind binary to run, set with `tailwind.runner` or `--runner`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunnerKind {
+    /// Try each runner in order: local, path, standalone, npx
+    #[default]
+    Auto,
+    /// The project's own install in `node_modules/.bin`
+    Local,
+    /// A `tailwindcss` binary on the PATH
+    Path,
+    /// The standalone cli managed by easywind, downloaded if missing
+    Standalone,
+    /// `npx` with the exact tailwind version the project uses
+    Npx,
+}
+
+impl Display for RunnerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Auto => write!(f, "auto"),
+            Self::Local => write!(f, "local"),
+            Self::Path => write!(f, "path"),
+            Self::Standalone => write!(f, "standalone"),
+            Self::Npx => write!(f, "npx"),
+        }
+    }
+}
+
+impl FromStr for RunnerKind {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "local" => Ok(Self::Local),
+            "path" => Ok(Self::Path),
+            "standalone" => Ok(Self::Standalone),
+            "npx" => Ok(Self::Npx),
+            _ => Err(eyre!(
+                "Unknown tailwind runner {s}, expected one of: auto, local, path, standalone, npx"
+            )),
+        }
+    }
+}
+
+/// The tailwind binary that was chosen for the project and the version it runs
+#[derive(Debug, Clone)]
+pub struct TailwindRunner {
+    pub kind: RunnerKind,
+    pub program: PathBuf,
+    pub version: TailwindVersion,
+}
+
+impl TailwindRunner {
+    /// Picks the runner to use, with `RunnerKind::Auto` the first one that is available and
+    /// matches the version the project wants is used
+    pub fn resolve(
+        kind: RunnerKind,
+        wanted: &TailwindVersion,
+        root_dir: &Path,
+        download: &DownloadOptions,
+    ) -> Result<Self> {
+        match kind {
+            RunnerKind::Local => Self::local(root_dir, wanted),
+            RunnerKind::Path => Self::path(root_dir, wanted),
+            RunnerKind::Standalone => Self::standalone(wanted, download),
+            RunnerKind::Npx => Self::npx(wanted),
+            RunnerKind::Auto => {
+                let found = [RunnerKind::Local, RunnerKind::Path]
+                    .into_iter()
+                    .find_map(|kind| {
+                        Self::resolve(kind, wanted, root_dir, download)
+                            .inspect_err(|error| debug!("Skipping {kind} tailwind: {error}"))
+                            .ok()
+                    });
+
+                if let Some(runner) = found {
+                    return Ok(runner);
+                }
+
+                // a pinned version always uses the standalone cli, npx would need to download it
+                // on every start
+                if wanted.is_installed() || wanted.pinned || validate::check_node_deps().is_err() {
+                    return Self::standalone(wanted, download);
+                }
+
+                Self::npx(wanted)
+            }
+        }
+    }
+
+    fn local(root_dir: &Path, wanted: &TailwindVersion) -> Result<Self> {
+        let bin_dir = root_dir.join("node_modules").join(".bin");
+
+        let program = which::which_in("tailwindcss", Some(&bin_dir), root_dir)
+            .map_err(|_| eyre!("tailwindcss not found in {}", bin_dir.display()))
+            .suggestion("Install it with `npm install --save-dev @tailwindcss/cli`")?;
+
+        Self::binary(RunnerKind::Local, program, wanted)
+    }
+
+    fn path(root_dir: &Path, wanted: &TailwindVersion) -> Result<Self> {
+        let program = which::which("tailwindcss")
+            .map_err(|_| eyre!("tailwindcss not found in PATH"))
+            .suggestion("Use `--runner standalone` to let easywind download tailwind")?;
+
+        // the PATH can include node_modules/.bin, that install is the local runner
+        if program.starts_with(root_dir.join("node_modules")) {
+            return Err(eyre!(
+                "tailwindcss in PATH is the project install {}",
+                program.display()
+            ));
+        }
+
+        Self::binary(RunnerKind::Path, program, wanted)
+    }
+
+    fn standalone(wanted: &TailwindVersion, download: &DownloadOptions) -> Result<Self> {
+        if !wanted.is_installed() {
+            installer::install_cli(&wanted.version, download)?;
+        }
+
+        Ok(Self {
+            kind: RunnerKind::Standalone,
+            program: wanted.cli_path(),
+            version: wanted.clone(),
+        })
+    }
+
+    fn npx(wanted: &TailwindVersion) -> Result<Self> {
+        validate::check_node_deps()?;
+
+        Ok(Self {
+            kind: RunnerKind::Npx,
+            program: PathBuf::from("npx"),
+            version: wanted.clone(),
+        })
+    }
+
+    // a binary we didn't install, only use it if it runs the version the project wants
+    fn binary(kind: RunnerKind, program: PathBuf, wanted: &TailwindVersion) -> Result<Self> {
+        let output = duct::cmd(&program, ["--help"])
+            .stderr_to_stdout()
+            .stdout_capture()
+            .unchecked()
+            .run()?;
+
+        let found = TailwindVersion::from_help_output(&String::from_utf8_lossy(&output.stdout))
+            .ok_or_else(|| eyre!("Unable to detect the version of {}", program.display()))?;
+
+        let matches = if wanted.pinned {
+            found.version == wanted.version
+        } else {
+            found.major == wanted.major
+        };
+
+        if !matches {
+            return Err(eyre!(
+                "{} is tailwind {found}, but the project uses {}",
+                program.display(),
+                if wanted.pinned {
+                    wanted.to_string()
+                } else {
+                    wanted.major.to_string()
+                }
+            ));
+        }
+
+        Ok(Self {
+            kind,
+            program,
+            version: found,
+        })
+    }
+
+    /// Command to run tailwind with the given args
+    pub fn command(&self, args: &[&str]) -> duct::Expression {
+        match self.kind {
+            RunnerKind::Npx => {
+                let package = format!(
+                    "{}@{}",
+                    self.version.major.npm_package(),
+                    self.version.version
+                );
+                let npx_args = ["--yes", package.as_str()]
+                    .into_iter()
+                    .chain(args.iter().copied());
+
+                duct::cmd(&self.program, npx_args)
+            }
+            _ => duct::cmd(&self.program, args),
+        }
+    }
+
+    /// Runner to try if this one fails to run, only npx falls back to the standalone cli
+    pub fn fallback(&self, download: &DownloadOptions) -> Option<Result<Self>> {
+        (self.kind == RunnerKind::Npx).then(|| {
+            info!("npx tailwind failed, falling back to the standalone cli");
+            Self::standalone(&self.version, download)
+        })
+    }
+}
+
+impl Display for TailwindRunner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            RunnerKind::Npx => write!(
+                f,
+                "tailwind {} from npx ({}@{})",
+                self.version,
+                self.version.major.npm_package(),
+                self.version.version
+            ),
+            kind => write!(
+                f,
+                "tailwind {} from {} ({kind})",
+                self.version,
+                self.program.display()
+            ),
+        }
+    }
+}
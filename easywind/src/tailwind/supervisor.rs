@@ -0,0 +1,240 @@
+use std::{
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    process::ExitStatus,
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use color_eyre::Help;
+use eyre::{eyre, Context, Result};
+use log::{error, info, warn};
+use notify::RecursiveMode;
+
+use crate::{
+    config::{self, Config},
+    consts::CONFIG_FILE_NAME,
+    watch,
+};
+
+use super::{
+    cli,
+    output::{self, BuildResult},
+    runner::TailwindRunner,
+    version::{self, TailwindVersion},
+    TailwindArgs,
+};
+
+static MAX_RESTARTS: u32 = 5;
+static RESTART_DELAY: Duration = Duration::from_secs(1);
+static MAX_RESTART_DELAY: Duration = Duration::from_secs(30);
+
+// a watcher that stayed up this long was healthy, a crash after that starts the count over
+static HEALTHY_AFTER: Duration = Duration::from_secs(30);
+
+enum Event {
+    Exited(Option<ExitStatus>),
+    ConfigChanged(PathBuf),
+}
+
+/// Runs the tailwind watcher and keeps it running, it is restarted with a backoff when it exits
+/// and right away when the tailwind or easywind config changes
+pub fn watch(mut args: TailwindArgs, mut runner: TailwindRunner) -> Result<()> {
+    let (events, receiver) = mpsc::channel();
+    let _watcher = watch_config(&args.root_dir, events.clone())?;
+
+    let mut crashes = 0;
+
+    loop {
+        let started = Instant::now();
+        let watcher = spawn(&args, &runner, events.clone())?;
+
+        match receiver.recv()? {
+            Event::ConfigChanged(path) => {
+                info!("{} changed, restarting tailwind", path.display());
+
+                watcher.kill()?;
+                wait_for_exit(&receiver)?;
+
+                crashes = 0;
+                (args, runner) = reload(args, runner, &path);
+            }
+
+            Event::Exited(status) => {
+                if started.elapsed() > HEALTHY_AFTER {
+                    crashes = 0;
+                }
+
+                crashes += 1;
+
+                let status = status
+                    .map(|status| status.to_string())
+                    .unwrap_or_else(|| "unknown status".to_string());
+
+                if crashes > MAX_RESTARTS {
+                    return Err(eyre!(
+                        "Tailwind watcher exited ({status}), giving up after {MAX_RESTARTS} restarts"
+                    ))
+                    .suggestion("Check the tailwind output above for errors")
+                    .suggestion("Run `easywind tailwind` to run tailwind on its own");
+                }
+
+                if let Some(fallback) = runner.fallback(&args.download) {
+                    runner = fallback?;
+                }
+
+                let delay = (RESTART_DELAY * 2u32.pow(crashes - 1)).min(MAX_RESTART_DELAY);
+                warn!(
+                    "Tailwind watcher exited ({status}), restarting in {}s",
+                    delay.as_secs()
+                );
+
+                // a config change is probably the fix, don't wait for the backoff
+                match receiver.recv_timeout(delay) {
+                    Ok(Event::ConfigChanged(path)) => {
+                        info!("{} changed, restarting tailwind", path.display());
+
+                        crashes = 0;
+                        (args, runner) = reload(args, runner, &path);
+                    }
+                    Ok(Event::Exited(_)) | Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => {
+                        return Err(eyre!("Tailwind supervisor channel closed"))
+                    }
+                }
+            }
+        }
+    }
+}
+
+type Watcher = Arc<duct::ReaderHandle>;
+
+fn spawn(args: &TailwindArgs, runner: &TailwindRunner, events: Sender<Event>) -> Result<Watcher> {
+    let mut tailwind_args = cli::base_args(args, runner.version.major)?;
+    tailwind_args.push("--watch");
+
+    let watcher = runner
+        .command(&tailwind_args)
+        .stderr_to_stdout()
+        .stdout_capture()
+        .dir(&args.root_dir)
+        .unchecked()
+        .reader()
+        .wrap_err_with(|| format!("Unable to start {runner}"))?;
+
+    let watcher = Arc::new(watcher);
+    let reader = watcher.clone();
+    let reloader = args.reloader.clone();
+
+    std::thread::spawn(move || {
+        for line in BufReader::new(&*reader).lines() {
+            match line {
+                Ok(line) => {
+                    let result = output::parse_line(&line);
+
+                    match result {
+                        Some(BuildResult::Failed(_)) => error!("{line}"),
+                        _ => info!("{line}"),
+                    }
+
+                    if let (Some(reloader), Some(result)) = (&reloader, result) {
+                        reloader.build_result(result);
+                    }
+                }
+                Err(error) => {
+                    error!("Unable to read tailwind output: {error}");
+                    break;
+                }
+            }
+        }
+
+        let status = reader.try_wait().ok().flatten().map(|output| output.status);
+        let _ = events.send(Event::Exited(status));
+    });
+
+    Ok(watcher)
+}
+
+fn wait_for_exit(receiver: &Receiver<Event>) -> Result<()> {
+    loop {
+        if let Event::Exited(_) = receiver.recv()? {
+            return Ok(());
+        }
+    }
+}
+
+// if the new config is invalid tailwind keeps running with the old one
+fn reload(
+    args: TailwindArgs,
+    runner: TailwindRunner,
+    changed: &Path,
+) -> (TailwindArgs, TailwindRunner) {
+    match try_reload(&args, changed) {
+        Ok((args, new_runner)) => {
+            if new_runner.version != runner.version {
+                info!("Using {new_runner}");
+            }
+
+            (args, new_runner)
+        }
+        Err(error) => {
+            error!("Unable to reload config, keeping the current tailwind: {error:?}");
+            (args, runner)
+        }
+    }
+}
+
+// picks up a newly pinned version or a switch between v3 and v4
+fn try_reload(args: &TailwindArgs, changed: &Path) -> Result<(TailwindArgs, TailwindRunner)> {
+    let mut args = args.clone();
+
+    if changed.file_name() == Some(CONFIG_FILE_NAME.as_ref()) {
+        let resolved = config::resolve(Config {
+            root_dir: Some(args.root_dir.clone()),
+            ..Default::default()
+        })?;
+
+        args.version = resolved.config.tailwind.version.clone();
+        args.download = (&resolved.config.tailwind).into();
+    }
+
+    let version = TailwindVersion::resolve(args.version.as_deref(), &args.root_dir, &args.input)?;
+    let runner = TailwindRunner::resolve(args.runner, &version, &args.root_dir, &args.download)?;
+
+    Ok((args, runner))
+}
+
+fn watch_config(root_dir: &Path, events: Sender<Event>) -> Result<watch::Watcher> {
+    // easywind.toml can live in a parent of the root dir
+    let project_dir = config::resolve(Config {
+        root_dir: Some(root_dir.to_path_buf()),
+        ..Default::default()
+    })
+    .ok()
+    .and_then(|resolved| resolved.project_file)
+    .and_then(|path| path.parent().map(Path::to_path_buf))
+    .filter(|dir| dir != root_dir);
+
+    let dirs = std::iter::once(root_dir.to_path_buf())
+        .chain(project_dir)
+        .collect::<Vec<_>>();
+
+    let mut watcher = watch::Watcher::new(Duration::from_millis(200), move |changed| {
+        for path in changed.into_iter().filter(|path| is_config_file(path)) {
+            let _ = events.send(Event::ConfigChanged(path));
+        }
+    })?;
+
+    for dir in &dirs {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+
+    Ok(watcher)
+}
+
+fn is_config_file(path: &Path) -> bool {
+    path.file_name() == Some(CONFIG_FILE_NAME.as_ref()) || version::is_config_file(path)
+}
@@ -0,0 +1,232 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use color_eyre::Help;
+use eyre::{eyre, Result};
+
+use crate::consts::{tailwind_cli_path, LATEST_TAILWIND_V3_VERSION, LATEST_TAILWIND_VERSION};
+
+static CONFIG_FILES: &[&str] = &[
+    "tailwind.config.js",
+    "tailwind.config.cjs",
+    "tailwind.config.mjs",
+    "tailwind.config.ts",
+];
+
+/// Major version of tailwind a project is written for, v3 projects use a `tailwind.config.js`
+/// and `@tailwind` directives, v4 projects are configured in css with `@import "tailwindcss"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TailwindMajor {
+    V3,
+    #[default]
+    V4,
+}
+
+impl TailwindMajor {
+    /// Detects the version from the input css, falling back to looking for a config file
+    pub fn detect(root_dir: &Path, input: &Path) -> Self {
+        let css = std::fs::read_to_string(input).unwrap_or_default();
+
+        if css.lines().any(is_v4_directive) {
+            return Self::V4;
+        }
+
+        if css
+            .lines()
+            .any(|line| line.trim_start().starts_with("@tailwind "))
+        {
+            return Self::V3;
+        }
+
+        if Self::config_file(root_dir).is_some() {
+            return Self::V3;
+        }
+
+        Self::default()
+    }
+
+    pub fn config_file(root_dir: &Path) -> Option<PathBuf> {
+        CONFIG_FILES
+            .iter()
+            .map(|file| root_dir.join(file))
+            .find(|path| path.exists())
+    }
+
+    pub fn latest_version(&self) -> &'static str {
+        match self {
+            Self::V3 => LATEST_TAILWIND_V3_VERSION,
+            Self::V4 => LATEST_TAILWIND_VERSION,
+        }
+    }
+
+    /// Package to run with npx, v4 moved the CLI into its own package
+    pub fn npm_package(&self) -> &'static str {
+        match self {
+            Self::V3 => "tailwindcss",
+            Self::V4 => "@tailwindcss/cli",
+        }
+    }
+}
+
+/// Whether the file is a tailwind v3 config file, ex: `tailwind.config.js`
+pub fn is_config_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| CONFIG_FILES.contains(&name))
+}
+
+fn is_v4_directive(line: &str) -> bool {
+    let line = line.trim_start();
+
+    [
+        "@import \"tailwindcss",
+        "@import 'tailwindcss",
+        "@theme",
+        "@source ",
+        "@plugin ",
+    ]
+    .iter()
+    .any(|directive| line.starts_with(directive))
+}
+
+impl Display for TailwindMajor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::V3 => write!(f, "v3"),
+            Self::V4 => write!(f, "v4"),
+        }
+    }
+}
+
+impl FromStr for TailwindMajor {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim_start_matches(['v', 'V']) {
+            "3" => Ok(Self::V3),
+            "4" => Ok(Self::V4),
+            _ => Err(eyre!("Unsupported tailwind version {s}, expected 3 or 4")),
+        }
+    }
+}
+
+/// An exact tailwind version, either pinned in the config or the latest release of the major
+/// version the project uses
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TailwindVersion {
+    pub version: String,
+    pub major: TailwindMajor,
+    /// Set in the config, a pinned version always uses the standalone cli
+    pub pinned: bool,
+}
+
+impl TailwindVersion {
+    pub fn parse(version: &str) -> Result<Self> {
+        let version = version.trim().trim_start_matches('v');
+
+        // allow pre-releases, ex: 4.0.0-beta.1, the version is part of paths in the cache dir so
+        // nothing but letters, digits and single dots get through
+        let (release, pre_release) = match version.split_once('-') {
+            Some((release, pre_release)) => (release, Some(pre_release)),
+            None => (version, None),
+        };
+        let parts = release.split('.').collect::<Vec<_>>();
+
+        let valid = parts.len() == 3
+            && parts
+                .iter()
+                .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+            && pre_release.is_none_or(|pre_release| {
+                pre_release.split('.').all(|identifier| {
+                    !identifier.is_empty() && identifier.chars().all(|c| c.is_ascii_alphanumeric())
+                })
+            });
+
+        if !valid {
+            return Err(eyre!("Invalid tailwind version: {version}"))
+                .suggestion("Use a full version number, ex: 4.1.13");
+        }
+
+        Ok(Self {
+            version: version.to_string(),
+            major: parts[0].parse()?,
+            pinned: true,
+        })
+    }
+
+    pub fn latest(major: TailwindMajor) -> Self {
+        Self {
+            version: major.latest_version().to_string(),
+            major,
+            pinned: false,
+        }
+    }
+
+    /// Uses the pinned version if there is one, otherwise the latest version of the detected major
+    pub fn resolve(pinned: Option<&str>, root_dir: &Path, input: &Path) -> Result<Self> {
+        match pinned {
+            Some(version) => Self::parse(version),
+            None => Ok(Self::latest(TailwindMajor::detect(root_dir, input))),
+        }
+    }
+
+    /// Reads the version from the output of `tailwindcss --help`, the first line is
+    /// `tailwindcss v3.4.17` for v3 and `≈ tailwindcss v4.1.13` for v4
+    pub fn from_help_output(output: &str) -> Option<Self> {
+        output.lines().find_map(|line| {
+            let (_, version) = line.split_once("tailwindcss v")?;
+            let version = version.split_whitespace().next()?;
+            Self::parse(version).ok()
+        })
+    }
+
+    /// Path to the standalone cli for this version
+    pub fn cli_path(&self) -> PathBuf {
+        tailwind_cli_path(&self.version)
+    }
+
+    pub fn is_installed(&self) -> bool {
+        self.cli_path().exists()
+    }
+}
+
+impl Display for TailwindVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v{}", self.version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_releases_and_pre_releases() {
+        let version = TailwindVersion::parse("v4.1.13").unwrap();
+        assert_eq!(version.version, "4.1.13");
+        assert_eq!(version.major, TailwindMajor::V4);
+
+        let version = TailwindVersion::parse("4.0.0-beta.1").unwrap();
+        assert_eq!(version.version, "4.0.0-beta.1");
+        assert_eq!(version.major, TailwindMajor::V4);
+    }
+
+    #[test]
+    fn rejects_versions_that_could_leave_the_cache_dir() {
+        for version in [
+            "4.0.0-../../x",
+            "4.0.0-beta/1",
+            "4.0.0-beta..1",
+            "4.0.0-",
+            "4.0.0-beta.",
+            "4.0.0-beta\\..",
+            "4.0",
+            "4.0.x",
+        ] {
+            assert!(TailwindVersion::parse(version).is_err(), "{version}");
+        }
+    }
+}
@@ -11,8 +11,10 @@ use strum::IntoEnumIterator;
 #[derive(EnumIter, Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum TemplateName {
     Index,
+    Error,
     ProjectIndex,
     ProjectCss,
+    ProjectCssV3,
     TailwindConfig,
 }
 
@@ -20,9 +22,11 @@ impl From<TemplateName> for &'static str {
     fn from(template: TemplateName) -> Self {
         match template {
             TemplateName::Index => "index.html",
+            TemplateName::Error => "error.html",
             TemplateName::ProjectIndex => "project_index.html",
             TemplateName::TailwindConfig => "tailwind.config.js",
             TemplateName::ProjectCss => "project_app_css.css",
+            TemplateName::ProjectCssV3 => "project_app_css_v3.css",
         }
     }
 }
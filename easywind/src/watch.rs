@@ -0,0 +1,75 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    time::{Duration, Instant},
+};
+
+use eyre::Result;
+use log::error;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+
+/// Watches files and calls back with the paths that changed, batched over the debounce window.
+/// Stops watching when dropped
+pub struct Watcher {
+    watcher: RecommendedWatcher,
+}
+
+impl Watcher {
+    pub fn new<F>(debounce: Duration, mut on_change: F) -> Result<Self>
+    where
+        F: FnMut(Vec<PathBuf>) + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        let watcher = notify::recommended_watcher(sender)?;
+
+        // the channel closes when the watcher is dropped, which ends the thread
+        std::thread::spawn(move || {
+            while let Ok(event) = receiver.recv() {
+                let mut changed = BTreeSet::new();
+                collect(event, &mut changed);
+
+                let deadline = Instant::now() + debounce;
+                let mut closed = false;
+
+                loop {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+
+                    match receiver.recv_timeout(timeout) {
+                        Ok(event) => collect(event, &mut changed),
+                        Err(RecvTimeoutError::Timeout) => break,
+                        Err(RecvTimeoutError::Disconnected) => {
+                            closed = true;
+                            break;
+                        }
+                    }
+                }
+
+                if !changed.is_empty() {
+                    on_change(changed.into_iter().collect());
+                }
+
+                if closed {
+                    return;
+                }
+            }
+        });
+
+        Ok(Self { watcher })
+    }
+
+    pub fn watch(&mut self, path: &Path, mode: RecursiveMode) -> Result<()> {
+        self.watcher.watch(path, mode)?;
+        Ok(())
+    }
+}
+
+fn collect(event: notify::Result<notify::Event>, changed: &mut BTreeSet<PathBuf>) {
+    match event {
+        // the watcher also reports files being opened and read, for ex: a proxied backend
+        // rendering a template or tailwind reading its config, those are not changes
+        Ok(event) if matches!(event.kind, EventKind::Access(_)) => {}
+        Ok(event) => changed.extend(event.paths),
+        Err(error) => error!("Error while watching for changes: {error}"),
+    }
+}
@@ -0,0 +1,119 @@
+(() => {
+  const OVERLAY_ID = "__easywind_overlay__";
+  const source = new EventSource("/__internal_only_easywind_livereload__");
+
+  let disconnected = false;
+
+  source.addEventListener("message", (event) => {
+    const message = JSON.parse(event.data);
+
+    switch (message.type) {
+      case "reload":
+        window.location.reload();
+        break;
+      case "css-swap":
+        swapCss(message.paths);
+        break;
+      case "build-error":
+        showOverlay(message.error);
+        break;
+      case "build-ok":
+        removeOverlay();
+        break;
+    }
+  });
+
+  // the server restarted, reload to pick up any changes made while it was down
+  source.addEventListener("open", () => {
+    if (disconnected) window.location.reload();
+  });
+
+  source.addEventListener("error", () => {
+    disconnected = true;
+  });
+
+  // replaces the matching stylesheets with a fresh copy, the old one is only removed once the
+  // new one loaded so the page doesn't flash unstyled. Reloads if none of them are on the page
+  function swapCss(paths) {
+    const links = [...document.querySelectorAll('link[rel="stylesheet"]')].filter((link) => {
+      const url = new URL(link.href, window.location.href);
+      if (url.origin !== window.location.origin) return false;
+
+      return paths.includes(decodeURIComponent(url.pathname));
+    });
+
+    if (links.length === 0) {
+      window.location.reload();
+      return;
+    }
+
+    for (const link of links) {
+      const url = new URL(link.href, window.location.href);
+      url.searchParams.set("easywind", Date.now().toString());
+
+      const fresh = link.cloneNode();
+      fresh.href = url.toString();
+      fresh.addEventListener("load", () => link.remove());
+      fresh.addEventListener("error", () => link.remove());
+
+      link.after(fresh);
+    }
+  }
+
+  function removeOverlay() {
+    document.getElementById(OVERLAY_ID)?.remove();
+  }
+
+  function showOverlay(error) {
+    removeOverlay();
+
+    const overlay = document.createElement("div");
+    overlay.id = OVERLAY_ID;
+    overlay.setAttribute(
+      "style",
+      [
+        "position: fixed",
+        "inset: auto 1rem 1rem 1rem",
+        "z-index: 2147483647",
+        "padding: 1rem 1.25rem",
+        "border-radius: 0.5rem",
+        "background: #1f1315",
+        "color: #fecaca",
+        "border: 1px solid #f87171",
+        "box-shadow: 0 10px 25px rgba(0, 0, 0, 0.4)",
+        "font: 14px/1.5 ui-monospace, SFMono-Regular, Menlo, monospace",
+        "white-space: pre-wrap",
+      ].join(";"),
+    );
+
+    const title = document.createElement("div");
+    title.setAttribute("style", "font-weight: bold; color: #f87171; margin-bottom: 0.5rem");
+    title.textContent = "Tailwind build failed";
+
+    const close = document.createElement("button");
+    close.textContent = "×";
+    close.title = "Dismiss";
+    close.setAttribute(
+      "style",
+      "position: absolute; top: 0.5rem; right: 0.75rem; background: none; border: none; color: inherit; font-size: 1.25rem; cursor: pointer",
+    );
+    close.addEventListener("click", removeOverlay);
+
+    const message = document.createElement("div");
+    message.textContent = error.message;
+
+    overlay.append(title, close, message);
+
+    if (error.file) {
+      const location = document.createElement("div");
+      location.setAttribute("style", "margin-top: 0.5rem; color: #fca5a5; opacity: 0.8");
+      location.textContent = [error.file, error.line, error.column]
+        .filter((part) => part != null)
+        .join(":");
+
+      overlay.append(location);
+    }
+
+    document.body.append(overlay);
+  }
+})();
@@ -1,34 +1,140 @@
-use crate::{InitArgs, ServerArgs, StartArgs, TailwindArgs};
+use easywind::{
+    config::{Config, ServerConfig, TailwindConfig},
+    consts::{CONFIG_FILE_NAME, GLOBAL_CONFIG_PATH},
+    tailwind::{
+        installer::{self, DownloadOptions},
+        version::TailwindVersion,
+    },
+};
+use eyre::Result;
+use log::info;
 
-impl From<ServerArgs> for easywind::server::ServerArgs {
+use crate::{
+    BuildArgs, BundleArgs, ConfigShowArgs, InitArgs, PreviewArgs, ServerArgs, StartArgs,
+    TailwindArgs, TailwindInstallArgs, TailwindSubcommand,
+};
+
+impl From<ServerArgs> for Config {
     fn from(args: ServerArgs) -> Self {
         Self {
             root_dir: args.root_dir,
-            port: args.port,
-            open: args.open,
+            email: flag(args.email, args.no_email),
+            server: ServerConfig {
+                host: args.host,
+                port: args.port,
+                strict_port: flag(args.strict_port, args.no_strict_port),
+                open: flag(args.open, args.no_open),
+                proxy: args.proxy,
+                cache: args.cache,
+                compress: flag(args.compress, args.no_compress),
+                spa: args.spa,
+                ..Default::default()
+            },
+            ..Default::default()
         }
     }
 }
 
-impl From<TailwindArgs> for easywind::tailwind::TailwindArgs {
+impl From<TailwindArgs> for Config {
     fn from(args: TailwindArgs) -> Self {
         Self {
             root_dir: args.root_dir,
-            input: args.input,
-            output: args.output,
-            watch: args.watch,
+            tailwind: TailwindConfig {
+                input: args.input,
+                output: args.output,
+                runner: args.runner,
+                ..Default::default()
+            },
+            ..Default::default()
         }
     }
 }
 
-impl From<StartArgs> for easywind::start::StartArgs {
+impl From<StartArgs> for Config {
     fn from(args: StartArgs) -> Self {
         Self {
             root_dir: args.root_dir,
-            port: args.port,
-            open: args.open,
-            input: args.input,
-            output: args.output,
+            email: flag(args.email, args.no_email),
+            server: ServerConfig {
+                host: args.host,
+                port: args.port,
+                strict_port: flag(args.strict_port, args.no_strict_port),
+                open: flag(args.open, args.no_open),
+                proxy: args.proxy,
+                cache: args.cache,
+                compress: flag(args.compress, args.no_compress),
+                spa: args.spa,
+                ..Default::default()
+            },
+            tailwind: TailwindConfig {
+                input: args.input,
+                output: args.output,
+                runner: args.runner,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl From<BuildArgs> for Config {
+    fn from(args: BuildArgs) -> Self {
+        Self {
+            root_dir: args.root_dir,
+            email: flag(args.email, args.no_email),
+            tailwind: TailwindConfig {
+                input: args.input,
+                runner: args.runner,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+impl From<BundleArgs> for Config {
+    fn from(args: BundleArgs) -> Self {
+        Self {
+            root_dir: args.root_dir,
+            tailwind: TailwindConfig {
+                input: args.input,
+                runner: args.runner,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+impl From<PreviewArgs> for Config {
+    fn from(args: PreviewArgs) -> Self {
+        Self {
+            root_dir: args.root_dir,
+            server: ServerConfig {
+                host: args.host,
+                port: args.port,
+                strict_port: flag(args.strict_port, args.no_strict_port),
+                open: flag(args.open, args.no_open),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+// a flag and its `--no-` counterpart, unset when neither is passed so the config file applies
+fn flag(yes: bool, no: bool) -> Option<bool> {
+    match (yes, no) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
+
+impl From<ConfigShowArgs> for Config {
+    fn from(args: ConfigShowArgs) -> Self {
+        Self {
+            root_dir: args.root_dir,
+            ..Default::default()
         }
     }
 }
@@ -37,10 +143,96 @@ impl From<InitArgs> for easywind::init::InitArgs {
     fn from(args: InitArgs) -> Self {
         Self {
             project_name: args.project_name,
+            tailwind: args.tailwind,
+            download: Default::default(),
         }
     }
 }
 
+pub(crate) fn tailwind_command(command: TailwindSubcommand) -> Result<()> {
+    let config = easywind::config::resolve(Config::default())?;
+    let tailwind_args: easywind::tailwind::TailwindArgs = config.clone().into();
+    let download = &tailwind_args.download;
+
+    let project_version = TailwindVersion::resolve(
+        tailwind_args.version.as_deref(),
+        &tailwind_args.root_dir,
+        &tailwind_args.input,
+    )?;
+
+    match command {
+        TailwindSubcommand::Install(TailwindInstallArgs {
+            version,
+            from: Some(from),
+            ..
+        }) => {
+            installer::import_cli(&from, version.as_deref())?;
+        }
+
+        TailwindSubcommand::Install(TailwindInstallArgs {
+            version,
+            from: None,
+            skip_checksum,
+        }) => {
+            let version = match version {
+                Some(version) => TailwindVersion::parse(&version)?,
+                None => project_version,
+            };
+
+            let download = DownloadOptions {
+                skip_checksum: skip_checksum || download.skip_checksum,
+                ..download.clone()
+            };
+
+            installer::install_cli(&version.version, &download)?;
+        }
+
+        TailwindSubcommand::List => {
+            let versions = installer::installed_versions()?;
+
+            if versions.is_empty() {
+                println!("No versions of the standalone tailwind cli are installed");
+            }
+
+            for version in versions {
+                let marker = if version.version == project_version.version {
+                    "*"
+                } else {
+                    " "
+                };
+
+                println!("{marker} {version}");
+            }
+        }
+
+        TailwindSubcommand::Use(args) => {
+            let version = TailwindVersion::parse(&args.version)?;
+
+            let config_file = if args.global {
+                GLOBAL_CONFIG_PATH.clone()
+            } else {
+                config
+                    .project_file
+                    .clone()
+                    .unwrap_or_else(|| config.root_dir().join(CONFIG_FILE_NAME))
+            };
+
+            if !version.is_installed() {
+                installer::install_cli(&version.version, download)?;
+            }
+
+            easywind::config::set_value(&config_file, "tailwind.version", &version.version)?;
+            info!("Using tailwind {version}, set in {}", config_file.display());
+        }
+
+        TailwindSubcommand::Remove(args) => {
+            installer::remove(&TailwindVersion::parse(&args.version)?)?;
+        }
+    }
+
+    Ok(())
+}
+
 pub fn get_styles() -> clap::builder::Styles {
     clap::builder::Styles::styled()
         .usage(
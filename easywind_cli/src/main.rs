@@ -4,6 +4,11 @@ pub mod cli;
 use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
+use easywind::{
+    consts::DEFAULT_BUILD_DIR,
+    server::cache::CacheMode,
+    tailwind::{runner::RunnerKind, version::TailwindMajor},
+};
 use eyre::Result;
 use pretty_env_logger::env_logger::Env;
 
@@ -26,13 +31,39 @@ enum Commands {
     #[command(visible_aliases = ["run", "s"])]
     Start(StartArgs),
 
+    /// Build the site for production, with minified css and rendered pages
+    #[command(visible_aliases = ["b"])]
+    Build(BuildArgs),
+
+    /// Bundle a page into a single html file that works offline, to send as an attachment
+    Bundle(BundleArgs),
+
+    /// Serve the output of `easywind build` like a production host, with long lived caching
+    Preview(PreviewArgs),
+
     /// Run a live reloading server to serve content
     #[command(name = "serve")]
     Server(ServerArgs),
 
-    /// Run the tailwind watcher that generates the CSS
+    /// Run the tailwind watcher that generates the CSS, or manage installed tailwind versions
     #[command(visible_aliases = ["t"])]
-    Tailwind(TailwindArgs),
+    Tailwind(TailwindCommand),
+
+    /// Inspect the project configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigCommand {
+    /// Show the resolved configuration and where each value came from
+    Show(ConfigShowArgs),
+}
+
+#[derive(Parser, Debug, Clone)]
+pub(crate) struct ConfigShowArgs {
+    /// Path to the root directory of the project, defaults to the current directory
+    pub root_dir: Option<PathBuf>,
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -42,21 +73,43 @@ pub(crate) struct InitArgs {
     /// This will be used to create a directory with the same name
     /// (usage: easywind init portfolio)
     pub project_name: String,
+
+    /// Major version of tailwind to scaffold the project for, 3 or 4
+    #[clap(long, default_value = "4")]
+    pub tailwind: TailwindMajor,
 }
 
 #[derive(Parser, Debug, Clone)]
 pub(crate) struct StartArgs {
-    #[clap(default_value = ".")]
-    pub root_dir: PathBuf,
+    /// Path to the root directory of the project, defaults to the directory of
+    /// `easywind.toml` or the current directory
+    pub root_dir: Option<PathBuf>,
+
+    /// Address to listen on, defaults to 127.0.0.1. Use 0.0.0.0 or :: to open the page from
+    /// other devices on your network
+    #[clap(long)]
+    pub host: Option<String>,
 
     /// Port the server shoud use, defaults to 3500
-    #[clap(short, long, default_value = "3500")]
-    pub port: u16,
+    #[clap(short, long)]
+    pub port: Option<u16>,
+
+    /// Exit if the port is taken instead of using the next available port
+    #[clap(long, overrides_with = "no_strict_port")]
+    pub strict_port: bool,
+
+    /// Use the next available port if the port is taken, overrides `easywind.toml`
+    #[clap(long, overrides_with = "strict_port")]
+    pub no_strict_port: bool,
 
     /// Open in your browser
-    #[clap(short = 'O', long)]
+    #[clap(short = 'O', long, overrides_with = "no_open")]
     pub open: bool,
 
+    /// Don't open in your browser, overrides `easywind.toml`
+    #[clap(long, overrides_with = "open")]
+    pub no_open: bool,
+
     /// Input css file to process
     #[clap(short, long)]
     pub input: Option<PathBuf>,
@@ -64,41 +117,296 @@ pub(crate) struct StartArgs {
     /// Where you want the final CSS file to be written
     #[clap(short, long)]
     pub output: Option<PathBuf>,
+
+    /// Forward all requests to a running backend and inject livereload into its html
+    /// (usage: easywind start --proxy http://localhost:4000)
+    #[clap(long)]
+    pub proxy: Option<String>,
+
+    /// Caching headers sent to the browser: none, revalidate or immutable, defaults to none
+    #[clap(long)]
+    pub cache: Option<CacheMode>,
+
+    /// Compress responses with gzip or brotli, like a production host would
+    #[clap(long, overrides_with = "no_compress")]
+    pub compress: bool,
+
+    /// Don't compress responses, overrides `easywind.toml`
+    #[clap(long, overrides_with = "compress")]
+    pub no_compress: bool,
+
+    /// Serve a page for html requests that match no file, for client side routing. The page is
+    /// relative to the root dir, defaults to index.html (usage: easywind start --spa=app.html)
+    #[clap(
+        long,
+        value_name = "FALLBACK",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "index.html"
+    )]
+    pub spa: Option<PathBuf>,
+
+    /// Preview html emails: css is inlined into `style` attributes, like `easywind build --email`
+    #[clap(long, overrides_with = "no_email")]
+    pub email: bool,
+
+    /// Serve pages as they are instead of inlining css, overrides `easywind.toml`
+    #[clap(long, overrides_with = "email")]
+    pub no_email: bool,
+
+    /// Which tailwind binary to run: auto, local, path, standalone or npx, defaults to auto
+    #[clap(long)]
+    pub runner: Option<RunnerKind>,
 }
 
 #[derive(Parser, Debug, Clone)]
 pub(crate) struct ServerArgs {
-    #[clap(default_value = ".")]
-    pub root_dir: PathBuf,
+    /// Path to the root directory of the project, defaults to the directory of
+    /// `easywind.toml` or the current directory
+    pub root_dir: Option<PathBuf>,
+
+    /// Address to listen on, defaults to 127.0.0.1. Use 0.0.0.0 or :: to open the page from
+    /// other devices on your network
+    #[clap(long)]
+    pub host: Option<String>,
 
     /// Port the server shoud use, defaults to 3500
-    #[clap(short, long, default_value = "3500")]
-    pub port: u16,
+    #[clap(short, long)]
+    pub port: Option<u16>,
+
+    /// Exit if the port is taken instead of using the next available port
+    #[clap(long, overrides_with = "no_strict_port")]
+    pub strict_port: bool,
+
+    /// Use the next available port if the port is taken, overrides `easywind.toml`
+    #[clap(long, overrides_with = "strict_port")]
+    pub no_strict_port: bool,
 
     /// Open in your browser
+    #[clap(short, long, overrides_with = "no_open")]
+    pub open: bool,
+
+    /// Don't open in your browser, overrides `easywind.toml`
+    #[clap(long, overrides_with = "open")]
+    pub no_open: bool,
+
+    /// Forward all requests to a running backend and inject livereload into its html
+    /// (usage: easywind serve --proxy http://localhost:4000)
+    #[clap(long)]
+    pub proxy: Option<String>,
+
+    /// Caching headers sent to the browser: none, revalidate or immutable, defaults to none
+    #[clap(long)]
+    pub cache: Option<CacheMode>,
+
+    /// Compress responses with gzip or brotli, like a production host would
+    #[clap(long, overrides_with = "no_compress")]
+    pub compress: bool,
+
+    /// Don't compress responses, overrides `easywind.toml`
+    #[clap(long, overrides_with = "compress")]
+    pub no_compress: bool,
+
+    /// Serve a page for html requests that match no file, for client side routing. The page is
+    /// relative to the root dir, defaults to index.html (usage: easywind serve --spa=app.html)
+    #[clap(
+        long,
+        value_name = "FALLBACK",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "index.html"
+    )]
+    pub spa: Option<PathBuf>,
+
+    /// Preview html emails: css is inlined into `style` attributes, like `easywind build --email`
+    #[clap(long, overrides_with = "no_email")]
+    pub email: bool,
+
+    /// Serve pages as they are instead of inlining css, overrides `easywind.toml`
+    #[clap(long, overrides_with = "email")]
+    pub no_email: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub(crate) struct BuildArgs {
+    /// Path to the root directory of the project, defaults to the directory of
+    /// `easywind.toml` or the current directory
+    pub root_dir: Option<PathBuf>,
+
+    /// Directory to write the site to, defaults to build inside the root dir
+    #[clap(long)]
+    pub out: Option<PathBuf>,
+
+    /// Input css file to process
+    #[clap(short, long)]
+    pub input: Option<PathBuf>,
+
+    /// Keep the original names of css, js, images and fonts instead of adding a content hash
+    #[clap(long)]
+    pub no_fingerprint: bool,
+
+    /// Build html emails: css is inlined into the `style` attribute of every element and only
+    /// media queries and other rules that can't be inlined stay in a `<style>` block
+    #[clap(long, overrides_with = "no_email")]
+    pub email: bool,
+
+    /// Build pages as they are instead of inlining css, overrides `easywind.toml`
+    #[clap(long, overrides_with = "email")]
+    pub no_email: bool,
+
+    /// Which tailwind binary to run: auto, local, path, standalone or npx, defaults to auto
+    #[clap(long)]
+    pub runner: Option<RunnerKind>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub(crate) struct BundleArgs {
+    /// The page to bundle, an html file, page template or markdown file
+    /// (usage: easywind bundle page.html -o page.bundled.html)
+    pub page: PathBuf,
+
+    /// Where to write the bundled html, defaults to `<name>.bundled.html` next to the page
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Path to the root directory of the project, defaults to the directory of
+    /// `easywind.toml` or the current directory
+    #[clap(long)]
+    pub root_dir: Option<PathBuf>,
+
+    /// Images and fonts bigger than this, in kB, are left as links instead of being inlined
+    #[clap(long, default_value_t = easywind::build::bundle::DEFAULT_INLINE_LIMIT)]
+    pub inline_limit: u64,
+
+    /// Input css file to process
+    #[clap(short, long)]
+    pub input: Option<PathBuf>,
+
+    /// Which tailwind binary to run: auto, local, path, standalone or npx, defaults to auto
+    #[clap(long)]
+    pub runner: Option<RunnerKind>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub(crate) struct PreviewArgs {
+    /// Path to the root directory of the project, defaults to the directory of
+    /// `easywind.toml` or the current directory
+    pub root_dir: Option<PathBuf>,
+
+    /// Directory the site was built to, defaults to build inside the root dir
+    #[clap(long)]
+    pub out: Option<PathBuf>,
+
+    /// Address to listen on, defaults to 127.0.0.1. Use 0.0.0.0 or :: to open the page from
+    /// other devices on your network
+    #[clap(long)]
+    pub host: Option<String>,
+
+    /// Port the server shoud use, defaults to 3500
     #[clap(short, long)]
+    pub port: Option<u16>,
+
+    /// Exit if the port is taken instead of using the next available port
+    #[clap(long, overrides_with = "no_strict_port")]
+    pub strict_port: bool,
+
+    /// Use the next available port if the port is taken, overrides `easywind.toml`
+    #[clap(long, overrides_with = "strict_port")]
+    pub no_strict_port: bool,
+
+    /// Open in your browser
+    #[clap(short, long, overrides_with = "no_open")]
     pub open: bool,
+
+    /// Don't open in your browser, overrides `easywind.toml`
+    #[clap(long, overrides_with = "open")]
+    pub no_open: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(args_conflicts_with_subcommands = true)]
+pub(crate) struct TailwindCommand {
+    #[command(subcommand)]
+    pub command: Option<TailwindSubcommand>,
+
+    #[command(flatten)]
+    pub args: TailwindArgs,
+}
+
+#[derive(Debug, Subcommand, Clone)]
+pub(crate) enum TailwindSubcommand {
+    /// Download a version of the standalone tailwind CLI, defaults to the version the project uses
+    Install(TailwindInstallArgs),
+
+    /// List the installed versions of the standalone tailwind CLI
+    #[command(visible_aliases = ["ls"])]
+    List,
+
+    /// Pin the project to a version of tailwind, installing it if needed
+    Use(TailwindUseArgs),
+
+    /// Remove an installed version of the standalone tailwind CLI
+    #[command(visible_aliases = ["rm"])]
+    Remove(TailwindRemoveArgs),
+}
+
+#[derive(Parser, Debug, Clone)]
+pub(crate) struct TailwindInstallArgs {
+    /// Version to install, ex: 4.1.13
+    pub version: Option<String>,
+
+    /// Install a standalone CLI you already downloaded instead, a path or a file:// url
+    /// (usage: easywind tailwind install --from ./tailwindcss-linux-x64)
+    #[clap(long)]
+    pub from: Option<String>,
+
+    /// Install the download even when the release has no checksums to verify it against
+    #[clap(long)]
+    pub skip_checksum: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub(crate) struct TailwindUseArgs {
+    /// Version to use, ex: 4.1.13
+    pub version: String,
+
+    /// Set the version in the global config instead of the project's `easywind.toml`
+    #[clap(short, long)]
+    pub global: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub(crate) struct TailwindRemoveArgs {
+    /// Version to remove, ex: 4.1.13
+    pub version: String,
 }
 
 #[derive(Parser, Debug, Clone)]
 pub(crate) struct TailwindArgs {
     /// Path to the root directory of the project. This is where the `tailwind.config.js` file is located.
     ///
-    /// Defaults to the current directory
-    #[clap(default_value = ".")]
-    pub root_dir: PathBuf,
+    /// Defaults to the directory of `easywind.toml` or the current directory
+    pub root_dir: Option<PathBuf>,
 
-    /// Input css file to process
-    #[clap(short, long, default_value = "src/app.css")]
-    pub input: PathBuf,
+    /// Input css file to process, defaults to src/app.css
+    #[clap(short, long)]
+    pub input: Option<PathBuf>,
 
-    /// Where you want the final CSS file to be written
-    #[clap(short, long, default_value = "dist/app.css")]
-    pub output: PathBuf,
+    /// Where you want the final CSS file to be written, defaults to dist/app.css
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
 
     /// Watch for changes in input CSS and recompile the output CSS
     #[clap(short, long)]
     pub watch: bool,
+
+    /// Minify the output CSS
+    #[clap(short, long)]
+    pub minify: bool,
+
+    /// Which tailwind binary to run: auto, local, path, standalone or npx, defaults to auto
+    #[clap(long)]
+    pub runner: Option<RunnerKind>,
 }
 
 #[tokio::main]
@@ -116,23 +424,108 @@ async fn main() -> Result<()> {
         CliArgs {
             command: Commands::Init(args),
         } => {
-            easywind::init::run(args.into())?;
+            let config = easywind::config::resolve(Default::default())?;
+
+            easywind::init::run(easywind::init::InitArgs {
+                download: (&config.config.tailwind).into(),
+                ..args.into()
+            })?;
         }
 
         CliArgs {
             command: Commands::Start(args),
         } => {
-            easywind::start::start(args.into()).await?;
+            let config = easywind::config::resolve(args.into())?;
+            easywind::start::start(config.into()).await?;
+        }
+        CliArgs {
+            command: Commands::Build(args),
+        } => {
+            let out = args.out.clone();
+            let no_fingerprint = args.no_fingerprint;
+            let config = easywind::config::resolve(args.into())?;
+
+            easywind::build::run(easywind::build::BuildArgs {
+                out_dir: out.unwrap_or_else(|| config.root_dir().join(DEFAULT_BUILD_DIR)),
+                fingerprint: !no_fingerprint,
+                ..config.into()
+            })?;
+        }
+        CliArgs {
+            command: Commands::Bundle(args),
+        } => {
+            let BundleArgs {
+                page,
+                output,
+                inline_limit,
+                ..
+            } = args.clone();
+
+            let config = easywind::config::resolve(args.into())?;
+
+            easywind::build::bundle::run(easywind::build::bundle::BundleArgs {
+                root_dir: config.root_dir(),
+                tailwind: config.into(),
+                page,
+                output,
+                inline_limit,
+            })?;
+        }
+        CliArgs {
+            command: Commands::Preview(args),
+        } => {
+            let out = args.out.clone();
+            let config = easywind::config::resolve(args.into())?;
+
+            easywind::build::preview(easywind::server::ServerArgs {
+                root_dir: out.unwrap_or_else(|| config.root_dir().join(DEFAULT_BUILD_DIR)),
+                ..config.into()
+            })
+            .await?;
         }
         CliArgs {
             command: Commands::Server(args),
         } => {
-            easywind::server::start(args.into()).await?;
+            let config = easywind::config::resolve(args.into())?;
+            easywind::server::start(config.into()).await?;
+        }
+        CliArgs {
+            command:
+                Commands::Tailwind(TailwindCommand {
+                    command: None,
+                    args,
+                }),
+        } => {
+            let watch = args.watch;
+            let minify = args.minify;
+            let config = easywind::config::resolve(args.into())?;
+
+            easywind::tailwind::start(easywind::tailwind::TailwindArgs {
+                watch,
+                minify,
+                ..config.into()
+            })?;
+        }
+        CliArgs {
+            command:
+                Commands::Tailwind(TailwindCommand {
+                    command: Some(command),
+                    ..
+                }),
+        } => {
+            cli::tailwind_command(command)?;
         }
         CliArgs {
-            command: Commands::Tailwind(args),
+            command: Commands::Config(ConfigCommand::Show(args)),
         } => {
-            easywind::tailwind::start(args.into())?;
+            let config = easywind::config::resolve(args.into())?;
+
+            match &config.project_file {
+                Some(path) => println!("# project config: {}\n", path.display()),
+                None => println!("# no easywind.toml found, using defaults\n"),
+            }
+
+            print!("{config}");
         }
     }
 